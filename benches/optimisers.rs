@@ -0,0 +1,134 @@
+//! Optimiser benchmarks on synthetic player pools.
+//!
+//! `ga_generations` reports how many generations per second the genetic
+//! algorithm runs; `time_to_target` how long the genetic algorithm and
+//! simulated annealing take to find a squad within `TARGET_GAP` of the pool's
+//! optimum, skipping an engine that stops short of it; and `time_to_optimum`
+//! how long the exact solver takes to prove the optimum.
+//!
+//! Run with `cargo bench`, or `cargo bench -- time_to_target/ga` for one
+//! group and engine.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use team_selector::{
+    select_best_team_exact, select_best_team_ga, select_best_team_sa, AnnealConfig, Player,
+    SelectionConfig, StopReason,
+};
+
+const POOL_SIZES: [usize; 3] = [200, 600, 2000];
+// Generations timed per GA run
+const GENERATIONS: usize = 50;
+// Share of the optimum's fitness the engines must reach
+const TARGET_GAP: f32 = 0.01;
+const CLUBS: usize = 20;
+// Positions and their share of the squad
+const POSITIONS: [(&str, u32); 4] = [("GK", 2), ("DEF", 5), ("MID", 5), ("FWD", 3)];
+
+// A pool with roughly the real game's mix of positions, prices and predicted
+// points: most players are cheap and score little, and dearer ones tend to
+// score more
+fn synthetic_players(count: usize) -> Vec<Player> {
+    let mut rng = ChaCha8Rng::seed_from_u64(count as u64);
+    let positions = WeightedIndex::new(POSITIONS.map(|(_, share)| share)).unwrap();
+    (0..count)
+        .map(|i| {
+            let (position, _) = POSITIONS[positions.sample(&mut rng)];
+            let value = (40.0 + 50.0 * rng.gen::<f32>().powi(3)).round();
+            let predicted_points = (value - 40.0) / 25.0 + 6.0 * rng.gen::<f32>().powi(2);
+            Player {
+                element: i as u32 + 1,
+                name: format!("{}_{}", position.to_lowercase(), i + 1),
+                value,
+                position: position.to_string(),
+                team: format!("club_{}", rng.gen_range(0..CLUBS)),
+                predicted_points,
+                gameweek_points: BTreeMap::new(),
+            }
+        })
+        .collect()
+}
+
+fn ga_generations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ga_generations");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(GENERATIONS as u64));
+    for size in POOL_SIZES {
+        let players = synthetic_players(size);
+        let config = SelectionConfig {
+            generations: GENERATIONS,
+            seed: Some(1),
+            ..SelectionConfig::default()
+        };
+        group.bench_with_input(BenchmarkId::from_parameter(size), &players, |b, players| {
+            b.iter(|| select_best_team_ga(players, &config))
+        });
+    }
+    group.finish();
+}
+
+fn time_to_target(c: &mut Criterion) {
+    let mut group = c.benchmark_group("time_to_target");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(10));
+    for size in POOL_SIZES {
+        let players = synthetic_players(size);
+        let optimum = select_best_team_exact(&players, None, &SelectionConfig::default())
+            .expect("synthetic pool has a valid squad")
+            .result
+            .score;
+        let config = SelectionConfig {
+            target_fitness: Some(optimum * (1.0 - TARGET_GAP)),
+            seed: Some(1),
+            ..SelectionConfig::default()
+        };
+
+        // Timing a run that never reaches the target would only time its
+        // full length, so check each engine gets there once first
+        let ga = select_best_team_ga(&players, &config).expect("synthetic pool has a valid squad");
+        if ga.stop_reason == StopReason::TargetReached {
+            group.bench_with_input(BenchmarkId::new("ga", size), &players, |b, players| {
+                b.iter(|| select_best_team_ga(players, &config))
+            });
+        } else {
+            eprintln!("Skipping time_to_target/ga/{}: {}", size, ga.stop_reason);
+        }
+        let anneal = select_best_team_sa(&players, &config, &AnnealConfig::default())
+            .expect("synthetic pool has a valid squad");
+        if config
+            .target_fitness
+            .is_some_and(|target| anneal.result.score >= target)
+        {
+            group.bench_with_input(BenchmarkId::new("anneal", size), &players, |b, players| {
+                b.iter(|| select_best_team_sa(players, &config, &AnnealConfig::default()))
+            });
+        } else {
+            eprintln!(
+                "Skipping time_to_target/anneal/{}: target fitness not reached",
+                size
+            );
+        }
+    }
+    group.finish();
+}
+
+fn time_to_optimum(c: &mut Criterion) {
+    let mut group = c.benchmark_group("time_to_optimum");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(10));
+    let config = SelectionConfig::default();
+    for size in POOL_SIZES {
+        let players = synthetic_players(size);
+        group.bench_with_input(BenchmarkId::new("exact", size), &players, |b, players| {
+            b.iter(|| select_best_team_exact(players, None, &config))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, ga_generations, time_to_target, time_to_optimum);
+criterion_main!(benches);
@@ -0,0 +1,218 @@
+use crate::ga::{generate_random_team, seeded_rng};
+use crate::player::Player;
+use crate::squad::{
+    check_feasibility, fitness, players_by_position, progress_bar, satisfies_constraints,
+    SelectionConfig, SelectionResult,
+};
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+// Default annealing parameters
+const ITERATIONS: u64 = 100_000;
+const INITIAL_TEMPERATURE: f32 = 5.0;
+const FINAL_TEMPERATURE: f32 = 0.01;
+// Moves between progress bar updates
+const PROGRESS_STEP: u64 = 1000;
+
+/// How the temperature falls from the initial to the final temperature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoolingSchedule {
+    /// By the same factor every move.
+    Geometric,
+    /// By the same amount every move.
+    Linear,
+}
+
+impl FromStr for CoolingSchedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "geometric" => Ok(CoolingSchedule::Geometric),
+            "linear" => Ok(CoolingSchedule::Linear),
+            _ => Err(format!(
+                "Unknown cooling schedule '{}', expected geometric or linear",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CoolingSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            CoolingSchedule::Geometric => "geometric",
+            CoolingSchedule::Linear => "linear",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Settings for simulated annealing.
+#[derive(Debug, Clone)]
+pub struct AnnealConfig {
+    /// Moves tried.
+    pub iterations: u64,
+    /// Temperature of the first move, in fitness points.
+    pub initial_temperature: f32,
+    /// Temperature of the last move.
+    pub final_temperature: f32,
+    pub schedule: CoolingSchedule,
+}
+
+impl Default for AnnealConfig {
+    fn default() -> Self {
+        AnnealConfig {
+            iterations: ITERATIONS,
+            initial_temperature: INITIAL_TEMPERATURE,
+            final_temperature: FINAL_TEMPERATURE,
+            schedule: CoolingSchedule::Geometric,
+        }
+    }
+}
+
+impl AnnealConfig {
+    /// Temperature at the given move.
+    pub fn temperature(&self, iteration: u64) -> f32 {
+        let progress = iteration as f32 / self.iterations.saturating_sub(1).max(1) as f32;
+        match self.schedule {
+            CoolingSchedule::Geometric => {
+                let ratio = self.final_temperature / self.initial_temperature;
+                self.initial_temperature * ratio.powf(progress)
+            }
+            CoolingSchedule::Linear => {
+                self.initial_temperature
+                    + (self.final_temperature - self.initial_temperature) * progress
+            }
+        }
+    }
+}
+
+/// The best squad simulated annealing found and how its run went.
+pub struct AnnealSolution {
+    pub result: SelectionResult,
+    /// Moves that were valid squads and were accepted.
+    pub accepted: u64,
+    /// Move at which the best squad was found, 0 for the starting squad.
+    pub best_iteration: u64,
+}
+
+// Swap one or two unlocked players for random players of the same position,
+// returning the new squad if it satisfies the squad rules
+fn neighbour<R: Rng + ?Sized>(
+    team: &[Player],
+    pools: &HashMap<&str, Vec<&Player>>,
+    config: &SelectionConfig,
+    rng: &mut R,
+) -> Option<Vec<Player>> {
+    let swaps = rng.gen_range(1..=2);
+    let mut next = team.to_vec();
+    for _ in 0..swaps {
+        let index = (0..next.len())
+            .filter(|&i| !config.locked.contains(&next[i].element))
+            .choose(rng)?;
+        let player = pools.get(next[index].position.as_str())?.choose(rng)?;
+        next[index] = (*player).clone();
+    }
+    satisfies_constraints(&next, config).then_some(next)
+}
+
+/// Anneal a squad from a random valid one, maximising `fitness`.
+///
+/// Each move swaps one or two players for others of the same position and is
+/// kept if it satisfies the squad rules and either improves fitness or passes
+/// the Metropolis test at the current temperature. The run stops early once a
+/// squad reaches `config.target_fitness`. With `config.seed` set the run is
+/// reproducible. Fails with the reason when no valid squad can be picked to
+/// start from.
+pub fn select_best_team_sa(
+    players: &[Player],
+    config: &SelectionConfig,
+    anneal: &AnnealConfig,
+) -> Result<AnnealSolution, String> {
+    check_feasibility(players, config)?;
+    let mut rng = seeded_rng(config.seed);
+    let pools = players_by_position(players, config);
+
+    let mut current = generate_random_team(players, config, &mut rng)?;
+    let mut current_score = fitness(&current, config);
+    let mut best = current.clone();
+    let mut best_score = current_score;
+    let mut best_iteration = 0;
+    let mut accepted = 0;
+
+    let progress_bar = progress_bar(anneal.iterations, config);
+
+    for iteration in 0..anneal.iterations {
+        if iteration % PROGRESS_STEP == 0 {
+            progress_bar.set_position(iteration);
+        }
+        let Some(next) = neighbour(&current, &pools, config, &mut rng) else {
+            continue;
+        };
+        let score = fitness(&next, config);
+        let delta = score - current_score;
+        let temperature = anneal.temperature(iteration);
+        if delta >= 0.0 || rng.gen::<f32>() < (delta / temperature).exp() {
+            current = next;
+            current_score = score;
+            accepted += 1;
+            if current_score > best_score {
+                best = current.clone();
+                best_score = current_score;
+                best_iteration = iteration + 1;
+                if config
+                    .target_fitness
+                    .is_some_and(|target| best_score >= target)
+                {
+                    break;
+                }
+            }
+        }
+    }
+
+    progress_bar.finish_with_message("Simulated annealing complete!");
+
+    Ok(AnnealSolution {
+        result: SelectionResult::new(best, config),
+        accepted,
+        best_iteration,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, check_squad};
+    use proptest::prelude::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn annealed_squads_are_valid(seed: u64, schedule in 0..2usize) {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let players = fixtures::players(150, seed);
+            let mut config = SelectionConfig {
+                seed: Some(seed),
+                ..SelectionConfig::default()
+            };
+            fixtures::lock_and_exclude(&players, &mut config, &mut rng);
+            let anneal = AnnealConfig {
+                iterations: 5000,
+                schedule: [CoolingSchedule::Geometric, CoolingSchedule::Linear][schedule],
+                ..AnnealConfig::default()
+            };
+            let solution = select_best_team_sa(&players, &config, &anneal).unwrap();
+
+            prop_assert_eq!(check_squad(&solution.result.squad, &config), Ok(()));
+            prop_assert_eq!(solution.result.score, fitness(&solution.result.squad, &config));
+            prop_assert!(solution.best_iteration <= anneal.iterations);
+        }
+    }
+}
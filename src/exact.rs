@@ -0,0 +1,475 @@
+use crate::player::Player;
+use crate::squad::{
+    fitness, satisfies_constraints, SelectionConfig, SelectionResult, BENCH_WEIGHT,
+};
+use std::collections::{HashMap, HashSet};
+
+// Bounds within this margin of the incumbent cannot improve on it
+const EPSILON: f64 = 1e-4;
+// Iterations used to refine the Lagrange multiplier on the budget
+const LAMBDA_STEPS: usize = 12;
+
+/// The proven optimal squad and the size of the search that found it.
+pub struct ExactSolution {
+    pub result: SelectionResult,
+    /// Branch-and-bound nodes visited.
+    pub nodes: u64,
+}
+
+// A player taking part in the search, with position and club mapped to indices
+struct Candidate {
+    player: usize,
+    position: usize,
+    club: usize,
+    points: f64,
+    value: f64,
+    locked: bool,
+}
+
+struct Search<'a> {
+    players: &'a [Player],
+    candidates: Vec<Candidate>,
+    slots: Vec<usize>,
+    starters: Vec<(usize, usize)>,
+    chosen: Vec<usize>,
+    position_counts: Vec<usize>,
+    club_counts: Vec<usize>,
+    cost: f64,
+    lambda: f64,
+    lambda_max: f64,
+    config: &'a SelectionConfig,
+    best: Option<Vec<Player>>,
+    best_score: f64,
+    nodes: u64,
+}
+
+/// Find the squad with the highest `fitness`, starting from an optional
+/// incumbent such as the genetic algorithm's answer. Returns `None` when no
+/// valid squad exists. Locked players are forced into every squad searched and
+/// excluded players never considered.
+///
+/// Branch-and-bound over players ordered by predicted points. Each node is
+/// bounded by a Lagrangian relaxation of the budget in which club caps are
+/// dropped and the squad is split per position into captain, starters and
+/// bench, so the bound is never below the best `fitness` reachable from that
+/// node.
+pub fn select_best_team_exact(
+    players: &[Player],
+    initial: Option<&[Player]>,
+    config: &SelectionConfig,
+) -> Option<ExactSolution> {
+    let rules = &config.rules;
+    let slots: Vec<usize> = rules.positions.iter().map(|r| r.squad).collect();
+    let starters: Vec<(usize, usize)> = rules
+        .positions
+        .iter()
+        .map(|r| (r.xi_min, r.xi_max))
+        .collect();
+
+    let mut clubs: HashMap<&str, usize> = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut candidates = Vec::new();
+    for (i, player) in players.iter().enumerate() {
+        let Some(position) = rules.position_index(&player.position) else {
+            continue;
+        };
+        if !seen.insert(player.element)
+            || player.value > config.max_value
+            || config.excluded.contains(&player.element)
+        {
+            continue;
+        }
+        let next_club = clubs.len();
+        let club = *clubs.entry(player.team.as_str()).or_insert(next_club);
+        candidates.push(Candidate {
+            player: i,
+            position,
+            club,
+            points: player.predicted_points as f64,
+            value: player.value as f64,
+            locked: config.locked.contains(&player.element),
+        });
+    }
+
+    let mut candidates = remove_dominated(
+        candidates,
+        &slots,
+        rules.squad_size(),
+        config.max_players_per_team,
+    );
+    candidates.sort_by(|a, b| {
+        b.points
+            .total_cmp(&a.points)
+            .then(a.value.total_cmp(&b.value))
+    });
+
+    let lambda_max = candidates
+        .iter()
+        .filter(|c| c.value > 0.0)
+        .map(|c| c.points / c.value)
+        .fold(0.0, f64::max);
+
+    let mut search = Search {
+        players,
+        candidates,
+        position_counts: vec![0; slots.len()],
+        slots,
+        starters,
+        chosen: Vec::new(),
+        club_counts: vec![0; clubs.len()],
+        cost: 0.0,
+        lambda: 0.0,
+        lambda_max,
+        config,
+        best: None,
+        best_score: f64::NEG_INFINITY,
+        nodes: 0,
+    };
+
+    if let Some(team) = initial {
+        if satisfies_constraints(team, config) {
+            search.best_score = fitness(team, config) as f64;
+            search.best = Some(team.to_vec());
+        }
+    }
+
+    search.branch(0);
+
+    let nodes = search.nodes;
+    search.best.map(|team| ExactSolution {
+        result: SelectionResult::new(team, config),
+        nodes,
+    })
+}
+
+// Drop players that can always be swapped for a cheaper, higher-scoring
+// player of the same position. With at most squad_size / club_cap full clubs
+// and fewer than `slots` dominators already in the squad, dominators spread
+// over more clubs than that guarantee such a swap stays legal. Locked players
+// are always kept.
+fn remove_dominated(
+    candidates: Vec<Candidate>,
+    slots: &[usize],
+    squad_size: usize,
+    club_cap: usize,
+) -> Vec<Candidate> {
+    let keep: Vec<bool> = candidates
+        .iter()
+        .map(|p| {
+            let clubs: HashSet<usize> = candidates
+                .iter()
+                .filter(|q| {
+                    q.position == p.position
+                        && q.player != p.player
+                        && q.points >= p.points
+                        && q.value <= p.value
+                        && (q.points > p.points || q.value < p.value || q.player < p.player)
+                })
+                .map(|q| q.club)
+                .collect();
+            p.locked || clubs.len() < squad_size / club_cap.max(1) + slots[p.position]
+        })
+        .collect();
+
+    candidates
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(c, keep)| keep.then_some(c))
+        .collect()
+}
+
+impl Search<'_> {
+    fn branch(&mut self, next: usize) {
+        self.nodes += 1;
+
+        if self.chosen.len() == self.config.rules.squad_size() {
+            if self.candidates[next..].iter().any(|c| c.locked) {
+                return;
+            }
+            let team: Vec<Player> = self
+                .chosen
+                .iter()
+                .map(|&c| self.players[self.candidates[c].player].clone())
+                .collect();
+            let score = fitness(&team, self.config) as f64;
+            if score > self.best_score {
+                self.best_score = score;
+                self.best = Some(team);
+            }
+            return;
+        }
+
+        if next == self.candidates.len() || !self.can_complete(next) {
+            return;
+        }
+        if self.bound(next) <= self.best_score + EPSILON {
+            return;
+        }
+
+        let candidate = &self.candidates[next];
+        let (position, club, value) = (candidate.position, candidate.club, candidate.value);
+        let locked = candidate.locked;
+        if self.position_counts[position] < self.slots[position]
+            && self.club_counts[club] < self.config.max_players_per_team
+            && self.cost + value <= self.config.max_value as f64
+        {
+            self.chosen.push(next);
+            self.position_counts[position] += 1;
+            self.club_counts[club] += 1;
+            self.cost += value;
+
+            self.branch(next + 1);
+
+            self.chosen.pop();
+            self.position_counts[position] -= 1;
+            self.club_counts[club] -= 1;
+            self.cost -= value;
+        }
+
+        if !locked {
+            self.branch(next + 1);
+        }
+    }
+
+    fn is_open(&self, candidate: &Candidate) -> bool {
+        self.position_counts[candidate.position] < self.slots[candidate.position]
+            && self.club_counts[candidate.club] < self.config.max_players_per_team
+            && self.cost + candidate.value <= self.config.max_value as f64
+    }
+
+    // Every remaining locked player must still fit, and enough affordable
+    // players must remain to fill every position
+    fn can_complete(&self, next: usize) -> bool {
+        let mut values: Vec<Vec<f64>> = vec![Vec::new(); self.slots.len()];
+        let mut locked = vec![0; self.slots.len()];
+        let mut cost = self.cost;
+        for candidate in &self.candidates[next..] {
+            if candidate.locked {
+                if !self.is_open(candidate) {
+                    return false;
+                }
+                locked[candidate.position] += 1;
+                cost += candidate.value;
+            } else if self.is_open(candidate) {
+                values[candidate.position].push(candidate.value);
+            }
+        }
+
+        for (position, values) in values.iter_mut().enumerate() {
+            let open = self.slots[position] - self.position_counts[position];
+            if locked[position] > open {
+                return false;
+            }
+            let needed = open - locked[position];
+            if values.len() < needed {
+                return false;
+            }
+            values.sort_by(|a, b| a.total_cmp(b));
+            cost += values[..needed].iter().sum::<f64>();
+        }
+        cost <= self.config.max_value as f64
+    }
+
+    // Any multiplier gives a valid bound, so try the last good one first and
+    // only search for a tighter one when it fails to prune
+    fn bound(&mut self, next: usize) -> f64 {
+        let threshold = self.best_score + EPSILON;
+        let mut best = self.bound_at(next, self.lambda);
+        if best <= threshold {
+            return best;
+        }
+
+        let (mut lo, mut hi) = (0.0, self.lambda_max);
+        for _ in 0..LAMBDA_STEPS {
+            let m1 = lo + (hi - lo) / 3.0;
+            let m2 = hi - (hi - lo) / 3.0;
+            let (b1, b2) = (self.bound_at(next, m1), self.bound_at(next, m2));
+            if b1 < best {
+                best = b1;
+                self.lambda = m1;
+            }
+            if b2 < best {
+                best = b2;
+                self.lambda = m2;
+            }
+            if best <= threshold {
+                break;
+            }
+            if b1 < b2 {
+                hi = m2;
+            } else {
+                lo = m1;
+            }
+        }
+        best
+    }
+
+    fn bound_at(&self, next: usize, lambda: f64) -> f64 {
+        // Chosen players precede `next`, so each list stays sorted by points;
+        // chosen and locked players are forced into the squad
+        let mut items: Vec<Vec<(f64, f64, bool)>> = vec![Vec::new(); self.slots.len()];
+        for &c in &self.chosen {
+            let candidate = &self.candidates[c];
+            items[candidate.position].push((candidate.points, candidate.value, true));
+        }
+        for candidate in &self.candidates[next..] {
+            if self.is_open(candidate) {
+                items[candidate.position].push((
+                    candidate.points,
+                    candidate.value,
+                    candidate.locked,
+                ));
+            }
+        }
+
+        // Best value for each number of starters, with and without the
+        // captain, across the positions seen so far
+        let mut totals = vec![[0.0, f64::NEG_INFINITY]];
+        for (position, items) in items.iter().enumerate() {
+            let table = position_table(
+                items,
+                self.slots[position],
+                self.starters[position],
+                self.config.captain_multiplier,
+                lambda,
+            );
+            let mut merged = vec![[f64::NEG_INFINITY; 2]; totals.len() + table.len() - 1];
+            for (i, a) in totals.iter().enumerate() {
+                for (k, b) in table.iter().enumerate() {
+                    let cell = &mut merged[i + k];
+                    cell[0] = cell[0].max(a[0] + b[0]);
+                    cell[1] = cell[1].max(a[0] + b[1]).max(a[1] + b[0]);
+                }
+            }
+            totals = merged;
+        }
+
+        lambda * self.config.max_value as f64
+            + totals
+                .get(self.config.rules.starters())
+                .map_or(f64::NEG_INFINITY, |t| t[1])
+    }
+}
+
+// For each starter count k, the best reduced score of filling `slots` places
+// from `items` (sorted by points, forced items always taken) where the top k
+// picked start and the rest sit on the bench, both without and with the first
+// starter as captain. Counts outside the formation limits are infeasible.
+fn position_table(
+    items: &[(f64, f64, bool)],
+    slots: usize,
+    (min_starters, max_starters): (usize, usize),
+    captain_multiplier: f32,
+    lambda: f64,
+) -> Vec<[f64; 2]> {
+    (0..=slots)
+        .map(|starters| {
+            if starters < min_starters || starters > max_starters {
+                return [f64::NEG_INFINITY; 2];
+            }
+            let mut row = [f64::NEG_INFINITY; 2];
+            for captain in [false, true] {
+                if captain && starters == 0 {
+                    continue;
+                }
+                let mut dp = vec![f64::NEG_INFINITY; slots + 1];
+                dp[0] = 0.0;
+                for &(points, value, forced) in items {
+                    let mut next = if forced {
+                        vec![f64::NEG_INFINITY; slots + 1]
+                    } else {
+                        dp.clone()
+                    };
+                    for picked in 0..slots {
+                        if dp[picked] == f64::NEG_INFINITY {
+                            continue;
+                        }
+                        let weight = if captain && picked == 0 {
+                            captain_multiplier as f64
+                        } else if picked < starters {
+                            1.0
+                        } else {
+                            BENCH_WEIGHT as f64
+                        };
+                        let score = dp[picked] + weight * points - lambda * value;
+                        next[picked + 1] = next[picked + 1].max(score);
+                    }
+                    dp = next;
+                }
+                row[captain as usize] = dp[slots];
+            }
+            row
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, player};
+    use proptest::prelude::*;
+    use rand::seq::index;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    // Positions of a pool small enough to try every squad of it
+    const POSITIONS: [&str; 14] = [
+        "GK", "GK", "GK", "DEF", "DEF", "DEF", "DEF", "MID", "MID", "MID", "MID", "FWD", "FWD",
+        "FWD",
+    ];
+
+    proptest! {
+        #[test]
+        fn matches_a_brute_force_search(seed: u64, locks in 0..3usize, exclusions in 0..3usize) {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let players: Vec<Player> = POSITIONS
+                .iter()
+                .enumerate()
+                .map(|(i, position)| {
+                    let value = rng.gen_range(40..=70) as f32;
+                    let points = rng.gen_range(0..=100) as f32 / 10.0;
+                    let team = format!("club_{}", rng.gen_range(0..4));
+                    player(i as u32 + 1, position, &team, value, points)
+                })
+                .collect();
+            let picked = index::sample(&mut rng, players.len(), locks + exclusions).into_vec();
+            let config = SelectionConfig {
+                rules: fixtures::six_man_rules(),
+                max_value: rng.gen_range(270..=340) as f32,
+                max_players_per_team: 2,
+                locked: picked[..locks].iter().map(|&i| players[i].element).collect(),
+                excluded: picked[locks..].iter().map(|&i| players[i].element).collect(),
+                ..SelectionConfig::default()
+            };
+
+            let best = (0u32..1 << players.len())
+                .filter(|mask| mask.count_ones() == 6)
+                .map(|mask| {
+                    players
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| mask & 1 << i != 0)
+                        .map(|(_, p)| p.clone())
+                        .collect::<Vec<_>>()
+                })
+                .filter(|team| satisfies_constraints(team, &config))
+                .map(|team| fitness(&team, &config))
+                .reduce(f32::max);
+            let solution = select_best_team_exact(&players, None, &config);
+
+            match (solution, best) {
+                (Some(solution), Some(best)) => {
+                    prop_assert!(satisfies_constraints(&solution.result.squad, &config));
+                    prop_assert!((solution.result.score - best).abs() < 1e-4);
+                }
+                (None, None) => {}
+                (solution, best) => prop_assert!(
+                    false,
+                    "exact found {:?}, brute force {:?}",
+                    solution.map(|s| s.result.score),
+                    best
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+// Player pools and a squad checker shared by the unit tests
+
+use crate::player::Player;
+use crate::rules::{PositionRule, SquadRules};
+use crate::squad::{check_player_lists, SelectionConfig};
+use rand::seq::IteratorRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Few enough clubs that the club cap often binds
+const CLUBS: usize = 8;
+// Positions in the proportions of a squad
+const POSITIONS: [&str; 15] = [
+    "GK", "GK", "DEF", "DEF", "DEF", "DEF", "DEF", "MID", "MID", "MID", "MID", "MID", "FWD", "FWD",
+    "FWD",
+];
+
+pub(crate) fn player(element: u32, position: &str, team: &str, value: f32, points: f32) -> Player {
+    Player {
+        element,
+        name: format!("{}_{}", position.to_lowercase(), element),
+        value,
+        position: position.to_string(),
+        team: team.to_string(),
+        predicted_points: points,
+        gameweek_points: BTreeMap::new(),
+    }
+}
+
+// A random pool of `count` players. Prices of 40 to 70 let the best squads
+// break the budget while any 14 players leave room for a 15th.
+pub(crate) fn players(count: usize, seed: u64) -> Vec<Player> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count)
+        .map(|i| {
+            let value = (40.0 + 30.0 * rng.gen::<f32>().powi(2)).round();
+            let points = (value - 40.0) / 10.0 + 5.0 * rng.gen::<f32>().powi(2);
+            let team = format!("club_{}", rng.gen_range(0..CLUBS));
+            player(
+                i as u32 + 1,
+                POSITIONS[i % POSITIONS.len()],
+                &team,
+                value,
+                points,
+            )
+        })
+        .collect()
+}
+
+// A valid squad of players 1 to 15, worth 750, with at most two from a club
+pub(crate) fn squad() -> Vec<Player> {
+    POSITIONS
+        .iter()
+        .enumerate()
+        .map(|(i, position)| {
+            player(
+                i as u32 + 1,
+                position,
+                &format!("club_{}", i % CLUBS),
+                50.0,
+                2.0,
+            )
+        })
+        .collect()
+}
+
+// Smaller draft-league rules: a 12-man squad with a bench of 3
+pub(crate) fn draft_rules() -> SquadRules {
+    SquadRules {
+        positions: vec![
+            PositionRule::new("GK", 1, 1, 1),
+            PositionRule::new("DEF", 4, 3, 4),
+            PositionRule::new("MID", 4, 3, 4),
+            PositionRule::new("FWD", 3, 1, 3),
+        ],
+        bench: 3,
+    }
+}
+
+// A six-man squad of which five start, small enough to try every squad of a
+// pool of a dozen or so players
+pub(crate) fn six_man_rules() -> SquadRules {
+    SquadRules {
+        positions: vec![
+            PositionRule::new("GK", 1, 1, 1),
+            PositionRule::new("DEF", 2, 1, 2),
+            PositionRule::new("MID", 2, 1, 2),
+            PositionRule::new("FWD", 1, 1, 1),
+        ],
+        bench: 1,
+    }
+}
+
+// A keeper and two forwards, all starting
+pub(crate) fn three_man_rules() -> SquadRules {
+    SquadRules {
+        positions: vec![
+            PositionRule::new("GK", 1, 1, 1),
+            PositionRule::new("FWD", 2, 1, 2),
+        ],
+        bench: 0,
+    }
+}
+
+// Lock and exclude a few random players of `players`, dropping the locks if
+// the locked players cannot share a squad
+pub(crate) fn lock_and_exclude<R: Rng + ?Sized>(
+    players: &[Player],
+    config: &mut SelectionConfig,
+    rng: &mut R,
+) {
+    let picked = players.iter().choose_multiple(rng, 6);
+    let (locked, excluded) = picked.split_at(rng.gen_range(0..=3));
+    config.locked = locked.iter().map(|p| p.element).collect();
+    config.excluded = excluded.iter().map(|p| p.element).collect();
+    if check_player_lists(players, config).is_err() {
+        config.locked.clear();
+    }
+}
+
+// Why `team` breaks the squad rules, checked from scratch without the crate's
+// own constraint code
+pub(crate) fn check_squad(team: &[Player], config: &SelectionConfig) -> Result<(), String> {
+    if team.len() != 15 {
+        return Err(format!("{} players", team.len()));
+    }
+    let elements: HashSet<u32> = team.iter().map(|p| p.element).collect();
+    if elements.len() != team.len() {
+        return Err("a player is picked twice".to_string());
+    }
+
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    let mut clubs: HashMap<&str, usize> = HashMap::new();
+    for player in team {
+        *positions.entry(player.position.as_str()).or_default() += 1;
+        *clubs.entry(player.team.as_str()).or_default() += 1;
+    }
+    for (position, count) in [("GK", 2), ("DEF", 5), ("MID", 5), ("FWD", 3)] {
+        if positions.get(position).copied().unwrap_or(0) != count {
+            return Err(format!("{:?} by position", positions));
+        }
+    }
+    if let Some((club, count)) = clubs
+        .iter()
+        .find(|(_, &count)| count > config.max_players_per_team)
+    {
+        return Err(format!("{} players from {}", count, club));
+    }
+
+    let value: f32 = team.iter().map(|p| p.value).sum();
+    if value > config.max_value {
+        return Err(format!("worth {}", value));
+    }
+    if let Some(element) = config.locked.iter().find(|e| !elements.contains(e)) {
+        return Err(format!("locked player {} is missing", element));
+    }
+    if let Some(element) = config.excluded.iter().find(|e| elements.contains(e)) {
+        return Err(format!("excluded player {} is picked", element));
+    }
+    Ok(())
+}
@@ -0,0 +1,813 @@
+use crate::player::Player;
+use crate::pool::{Pool, Squad};
+use crate::squad::{check_feasibility, progress_bar, SelectionConfig, SelectionResult};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Instant;
+
+// The seeded generator, or one seeded from the OS when no seed is given
+pub(crate) fn seeded_rng(seed: Option<u64>) -> ChaCha8Rng {
+    match seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_entropy(),
+    }
+}
+
+// Generate a random team satisfying constraints, starting from the locked
+// players, or say why none was found
+pub(crate) fn generate_random_team<R: Rng + ?Sized>(
+    players: &[Player],
+    config: &SelectionConfig,
+    rng: &mut R,
+) -> Result<Vec<Player>, String> {
+    let pool = Pool::new(players, config);
+    Ok(pool.to_players(&pool.random_squad(rng)?))
+}
+
+// Initialize population
+fn create_initial_population<R: Rng + ?Sized>(
+    pool: &Pool,
+    rng: &mut R,
+) -> Result<Vec<Squad>, String> {
+    (0..pool.config.population_size)
+        .map(|_| pool.random_squad(rng))
+        .collect()
+}
+
+// Draws of a random replacement tried before scanning every candidate
+const SAMPLE_TRIES: usize = 8;
+
+// Swaps tried by `repair` before a child is given up on
+const REPAIR_STEPS: usize = 30;
+
+/// How the children bred by crossover turned out over a run.
+#[derive(Debug, Clone, Default)]
+pub struct CrossoverStats {
+    /// Children bred.
+    pub children: u64,
+    /// Children that differ from both of their parents.
+    pub new: u64,
+    /// Children that needed players swapped to keep the club cap and budget.
+    pub repaired: u64,
+    /// Children that could not be repaired and were replaced by a parent.
+    pub failed: u64,
+}
+
+impl CrossoverStats {
+    /// Share of children that differ from both parents.
+    pub fn new_rate(&self) -> f32 {
+        if self.children == 0 {
+            0.0
+        } else {
+            self.new as f32 / self.children as f32
+        }
+    }
+
+    fn record(&mut self, repair: Repair, new: bool) {
+        self.children += 1;
+        self.new += new as u64;
+        self.repaired += (repair == Repair::Repaired) as u64;
+        self.failed += (repair == Repair::Failed) as u64;
+    }
+
+    fn add(&mut self, other: &CrossoverStats) {
+        self.children += other.children;
+        self.new += other.new;
+        self.repaired += other.repaired;
+        self.failed += other.failed;
+    }
+}
+
+// Whether a crossover child needed repairing
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Repair {
+    None,
+    Repaired,
+    Failed,
+}
+
+fn same_squad(a: &[u32], b: &[u32]) -> bool {
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort_unstable();
+    b.sort_unstable();
+    a == b
+}
+
+// Take each position's whole group of players from one parent or the other,
+// so the child keeps the squad's composition, then repair the club cap and
+// budget. Falls back to a copy of a parent if the repair fails.
+fn crossover<R: Rng + ?Sized>(
+    parent1: &[u32],
+    parent2: &[u32],
+    pool: &Pool,
+    rng: &mut R,
+) -> (Squad, Repair) {
+    let mut positions: Vec<u8> = parent1.iter().map(|&i| pool.entry(i).position).collect();
+    positions.sort_unstable();
+    positions.dedup();
+    let mut from_first: Vec<bool> = positions.iter().map(|_| rng.gen()).collect();
+    // Take at least one group from each parent
+    if from_first.iter().all(|&f| f == from_first[0]) {
+        let i = rng.gen_range(0..from_first.len());
+        from_first[i] = !from_first[i];
+    }
+
+    let mut child = Vec::with_capacity(parent1.len());
+    for (&position, first) in positions.iter().zip(from_first) {
+        let parent = if first { parent1 } else { parent2 };
+        child.extend(
+            parent
+                .iter()
+                .filter(|&&i| pool.entry(i).position == position),
+        );
+    }
+
+    let mut repair = Repair::None;
+    if !pool.is_valid(&child) {
+        repair = if repair_team(&mut child, parent2, pool, rng) {
+            Repair::Repaired
+        } else {
+            Repair::Failed
+        };
+    }
+
+    if repair != Repair::Failed && pool.is_valid(&child) {
+        (child, repair)
+    } else if rng.gen::<f32>() < 0.5 {
+        (parent1.to_vec(), Repair::Failed)
+    } else {
+        (parent2.to_vec(), Repair::Failed)
+    }
+}
+
+// Swap unlocked players over the club cap, then while over budget, for a
+// same-position replacement that fixes the problem, drawn first from `donor`
+// and then from all players. Returns false if no replacement can be found.
+fn repair_team<R: Rng + ?Sized>(team: &mut [u32], donor: &[u32], pool: &Pool, rng: &mut R) -> bool {
+    let cap = pool.config.max_players_per_team;
+    for _ in 0..REPAIR_STEPS {
+        let club_counts = pool.club_counts(team);
+        let unlocked = (0..team.len()).filter(|&k| !pool.entry(team[k]).locked);
+
+        let over_cap: Vec<usize> = unlocked
+            .clone()
+            .filter(|&k| club_counts[pool.entry(team[k]).club as usize] > cap)
+            .collect();
+        let over_budget = pool.value(team) > pool.config.max_value;
+        let index = match over_cap.choose(rng) {
+            Some(&k) => k,
+            None if over_budget => match unlocked.choose(rng) {
+                Some(k) => k,
+                None => return false,
+            },
+            None => return true,
+        };
+
+        let out = *pool.entry(team[index]);
+        let fits = |&i: &u32| {
+            let entry = pool.entry(i);
+            let club_count = club_counts[entry.club as usize];
+            entry.position == out.position
+                && !entry.excluded
+                && !team.iter().any(|&j| pool.entry(j).element == entry.element)
+                && if over_cap.is_empty() {
+                    entry.value < out.value && (entry.club == out.club || club_count < cap)
+                } else {
+                    entry.club != out.club && club_count < cap
+                }
+        };
+        let replacement = match donor.iter().copied().filter(fits).choose(rng) {
+            Some(i) => i,
+            None => match pool.by_position[out.position as usize]
+                .iter()
+                .copied()
+                .filter(fits)
+                .choose(rng)
+            {
+                Some(i) => i,
+                None => return false,
+            },
+        };
+        team[index] = replacement;
+    }
+    false
+}
+
+// Share of mutations that swap a pair of players, so that one can be upgraded
+// by downgrading the other to stay within budget
+const PAIR_SWAP_RATE: f32 = 0.5;
+
+// A random player of the same position who can take the place of
+// `team[index]` without breaking the club cap, for at most `budget`
+fn pick_replacement<R: Rng + ?Sized>(
+    team: &[u32],
+    index: usize,
+    pool: &Pool,
+    budget: f32,
+    rng: &mut R,
+) -> Option<u32> {
+    let out = pool.entry(team[index]);
+    let club_counts = pool.club_counts(team);
+    let fits = |&i: &u32| {
+        let entry = pool.entry(i);
+        let others = club_counts[entry.club as usize] - (entry.club == out.club) as usize;
+        entry.value <= budget
+            && others < pool.config.max_players_per_team
+            && !team.iter().any(|&j| pool.entry(j).element == entry.element)
+    };
+
+    // Most players usually fit, so a few random draws nearly always find one
+    let candidates = &pool.by_position[out.position as usize];
+    for _ in 0..SAMPLE_TRIES {
+        let i = *candidates.choose(rng)?;
+        if fits(&i) {
+            return Some(i);
+        }
+    }
+    candidates.iter().copied().filter(fits).choose(rng)
+}
+
+// Replace `team[index]` with a random affordable player that fits the squad
+fn swap_one<R: Rng + ?Sized>(team: &mut [u32], index: usize, pool: &Pool, rng: &mut R) -> bool {
+    let others = pool.value(team) - pool.entry(team[index]).value;
+    let budget = pool.config.max_value - others;
+    match pick_replacement(team, index, pool, budget, rng) {
+        Some(i) => {
+            team[index] = i;
+            true
+        }
+        None => false,
+    }
+}
+
+// Replace `team[index]` with any player that fits the squad, then another
+// unlocked player with one that brings the squad back within budget
+fn swap_pair<R: Rng + ?Sized>(team: &mut [u32], index: usize, pool: &Pool, rng: &mut R) -> bool {
+    let other = (0..team.len())
+        .filter(|&k| k != index && !pool.entry(team[k]).locked)
+        .choose(rng);
+    let replacement = pick_replacement(team, index, pool, f32::INFINITY, rng);
+    let (Some(other), Some(replacement)) = (other, replacement) else {
+        return false;
+    };
+
+    let out = std::mem::replace(&mut team[index], replacement);
+    if swap_one(team, other, pool, rng) {
+        true
+    } else {
+        team[index] = out;
+        false
+    }
+}
+
+// Swap each unlocked player out with probability `mutation_rate`, for a
+// same-position replacement that keeps the squad valid
+fn mutate<R: Rng + ?Sized>(team: &mut [u32], pool: &Pool, rng: &mut R) {
+    for index in 0..team.len() {
+        if pool.entry(team[index]).locked || rng.gen::<f32>() >= pool.config.mutation_rate {
+            continue;
+        }
+        if rng.gen::<f32>() < PAIR_SWAP_RATE {
+            swap_pair(team, index, pool, rng);
+        } else {
+            swap_one(team, index, pool, rng);
+        }
+    }
+}
+
+/// How the genetic algorithm picks the parents of each child.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParentSelection {
+    /// Uniformly from the better half of the population.
+    Truncation,
+    /// The best of `tournament_size` squads drawn at random.
+    Tournament,
+    /// With probability proportional to fitness.
+    Roulette,
+    /// With probability proportional to rank, the worst squad ranking 1.
+    Rank,
+}
+
+impl FromStr for ParentSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "truncation" => Ok(ParentSelection::Truncation),
+            "tournament" => Ok(ParentSelection::Tournament),
+            "roulette" => Ok(ParentSelection::Roulette),
+            "rank" => Ok(ParentSelection::Rank),
+            _ => Err(format!(
+                "Unknown selection '{}', expected truncation, tournament, roulette or rank",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ParentSelection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ParentSelection::Truncation => "truncation",
+            ParentSelection::Tournament => "tournament",
+            ParentSelection::Roulette => "roulette",
+            ParentSelection::Rank => "rank",
+        };
+        f.write_str(name)
+    }
+}
+
+// Picks parents from a population ranked best first
+struct Selector {
+    strategy: ParentSelection,
+    size: usize,
+    tournament_size: usize,
+    weights: Option<WeightedIndex<f32>>,
+}
+
+impl Selector {
+    fn new(ranked: &[(f32, Squad)], config: &SelectionConfig) -> Self {
+        let weights = match config.selection {
+            ParentSelection::Roulette => WeightedIndex::new(ranked.iter().map(|r| r.0.max(0.0))),
+            ParentSelection::Rank => WeightedIndex::new((1..=ranked.len()).rev().map(|r| r as f32)),
+            _ => Ok(WeightedIndex::new([1.0]).unwrap()),
+        };
+        Selector {
+            strategy: config.selection,
+            size: ranked.len(),
+            tournament_size: config.tournament_size.max(1),
+            // All-zero fitness leaves roulette picking uniformly
+            weights: weights.ok(),
+        }
+    }
+
+    fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        match (self.strategy, &self.weights) {
+            (ParentSelection::Truncation, _) => rng.gen_range(0..(self.size / 2).max(1)),
+            (ParentSelection::Tournament, _) => (0..self.tournament_size)
+                .map(|_| rng.gen_range(0..self.size))
+                .min()
+                .unwrap(),
+            (_, Some(weights)) => weights.sample(rng),
+            (_, None) => rng.gen_range(0..self.size),
+        }
+    }
+}
+
+// Keep the best distinct squads seen so far, best first
+fn update_hall_of_fame(hall_of_fame: &mut Vec<(f32, Squad)>, ranked: &[(f32, Squad)], size: usize) {
+    for (score, team) in ranked.iter().take(size) {
+        if hall_of_fame.iter().any(|(_, t)| same_squad(t, team)) {
+            continue;
+        }
+        let at = hall_of_fame.partition_point(|(s, _)| s >= score);
+        if at < size {
+            hall_of_fame.insert(at, (*score, team.clone()));
+            hall_of_fame.truncate(size);
+        }
+    }
+}
+
+// Evaluate fitness in parallel and sort best first
+fn rank(population: Vec<Squad>, pool: &Pool) -> Vec<(f32, Squad)> {
+    let mut ranked: Vec<(f32, Squad)> = population
+        .into_par_iter()
+        .map(|squad| (pool.fitness(&squad), squad))
+        .collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    ranked
+}
+
+// Share of distinct squads in a population
+fn diversity<'a>(squads: impl Iterator<Item = &'a [u32]>) -> f32 {
+    let mut count = 0;
+    let squads: HashSet<Squad> = squads
+        .map(|squad| {
+            count += 1;
+            let mut squad = squad.to_vec();
+            squad.sort_unstable();
+            squad
+        })
+        .collect();
+    squads.len() as f32 / count.max(1) as f32
+}
+
+/// Why the genetic algorithm stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// Every one of `config.generations` ran.
+    Generations,
+    /// The best fitness did not improve for `config.patience` generations.
+    Stalled,
+    /// The best fitness reached `config.target_fitness`.
+    TargetReached,
+    /// The run took longer than `config.time_limit`.
+    TimeLimit,
+    /// Population diversity fell below `config.min_diversity`.
+    Converged,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            StopReason::Generations => "generation limit reached",
+            StopReason::Stalled => "no improvement",
+            StopReason::TargetReached => "target fitness reached",
+            StopReason::TimeLimit => "time limit reached",
+            StopReason::Converged => "population converged",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// One generation of a genetic algorithm run: its population's fitness and
+/// diversity, and how the children bred for it came out of crossover.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GenerationStats {
+    /// Generation number, 0 for the initial population.
+    pub generation: usize,
+    pub best_fitness: f32,
+    pub mean_fitness: f32,
+    pub worst_fitness: f32,
+    /// Share of distinct squads across all islands.
+    pub diversity: f32,
+    /// Children bred for this generation, besides the elite.
+    pub children: u64,
+    /// Children that differ from both of their parents.
+    pub new_children: u64,
+    /// Valid children that needed a repair after crossover.
+    pub repaired_children: u64,
+    /// Children replaced by a copy of a parent because repair failed.
+    pub parent_copies: u64,
+}
+
+/// The genetic algorithm's best squad and how its run went.
+pub struct GaSolution {
+    /// The best squad seen in any generation.
+    pub result: SelectionResult,
+    /// The best distinct squads seen, best first, starting with `result`.
+    pub hall_of_fame: Vec<SelectionResult>,
+    pub crossover: CrossoverStats,
+    /// Generations bred before the run stopped.
+    pub generations: usize,
+    pub stop_reason: StopReason,
+    /// One entry per generation, starting with the initial population.
+    pub history: Vec<GenerationStats>,
+}
+
+// Carry the elite over, then breed the rest of the next generation with
+// crossover and mutation, each child drawing from its own generator seeded
+// from `rng`
+fn breed<R: Rng + ?Sized>(
+    ranked: &[(f32, Squad)],
+    pool: &Pool,
+    rng: &mut R,
+) -> (Vec<Squad>, CrossoverStats) {
+    let config = pool.config;
+    let selector = Selector::new(ranked, config);
+    // Always breed at least one child, or the run would stand still
+    let elite = config
+        .elite_count
+        .min(config.population_size.saturating_sub(1));
+    let seeds: Vec<u64> = (elite..config.population_size).map(|_| rng.gen()).collect();
+    let children: Vec<(Squad, Repair, bool)> = seeds
+        .into_par_iter()
+        .map(|seed| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let parent1 = &ranked[selector.pick(&mut rng)].1;
+            let parent2 = &ranked[selector.pick(&mut rng)].1;
+            let (mut child, repair) = crossover(parent1, parent2, pool, &mut rng);
+            let new = !same_squad(&child, parent1) && !same_squad(&child, parent2);
+            mutate(&mut child, pool, &mut rng);
+            (child, repair, new)
+        })
+        .collect();
+
+    let mut stats = CrossoverStats::default();
+    let mut population: Vec<Squad> = ranked
+        .iter()
+        .take(elite)
+        .map(|(_, team)| team.clone())
+        .collect();
+    for (child, repair, new) in children {
+        stats.record(repair, new);
+        population.push(child);
+    }
+    (population, stats)
+}
+
+// Send copies of each island's best squads to the next island in a ring,
+// replacing its worst
+fn migrate(islands: &mut [Vec<(f32, Squad)>], size: usize) {
+    let migrants: Vec<Vec<(f32, Squad)>> = islands
+        .iter()
+        .map(|ranked| ranked.iter().take(size).cloned().collect())
+        .collect();
+    let count = islands.len();
+    for (k, migrants) in migrants.into_iter().enumerate() {
+        let ranked = &mut islands[(k + 1) % count];
+        let keep = ranked.len().saturating_sub(migrants.len());
+        ranked.truncate(keep);
+        ranked.extend(migrants);
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    }
+}
+
+/// Evolve a squad with the genetic algorithm.
+///
+/// `config.islands` populations of `config.population_size` evolve side by
+/// side, and every `config.migration_interval` generations each sends copies
+/// of its `config.migration_size` best squads to the next, replacing its
+/// worst. Each generation keeps its `config.elite_count` best squads unchanged
+/// and breeds the rest from parents picked by `config.selection`. The run
+/// stops after `config.generations`, or earlier on any of the optional
+/// stopping criteria in `config`. With `config.seed` set and no time limit,
+/// the same players and settings always give the same squad, however rayon
+/// schedules the work.
+///
+/// Fails with the reason when `config.population_size` is zero or no valid
+/// squad can be picked, as found by `check_feasibility` or while building the
+/// first populations.
+pub fn select_best_team_ga(
+    players: &[Player],
+    config: &SelectionConfig,
+) -> Result<GaSolution, String> {
+    if config.population_size == 0 {
+        return Err("The population size must be at least 1".to_string());
+    }
+    check_feasibility(players, config)?;
+    let mut stats = CrossoverStats::default();
+    let mut rng = seeded_rng(config.seed);
+    let pool = Pool::new(players, config);
+    let mut islands: Vec<(Vec<Squad>, ChaCha8Rng)> = (0..config.islands.max(1))
+        .map(|_| {
+            let mut island_rng = ChaCha8Rng::seed_from_u64(rng.gen());
+            let population = create_initial_population(&pool, &mut island_rng)?;
+            Ok((population, island_rng))
+        })
+        .collect::<Result<_, String>>()?;
+    let hall_of_fame_size = config.hall_of_fame_size.max(1);
+    let mut hall_of_fame: Vec<(f32, Squad)> = Vec::new();
+    let start = Instant::now();
+    let mut generation = 0;
+    let mut improved = 0;
+    let mut history = Vec::new();
+    let mut bred = CrossoverStats::default();
+
+    let progress_bar = progress_bar(config.generations as u64, config);
+
+    let stop_reason = loop {
+        let (populations, rngs): (Vec<_>, Vec<_>) = islands.into_iter().unzip();
+        let mut ranked: Vec<Vec<(f32, Squad)>> = populations
+            .into_par_iter()
+            .map(|population| rank(population, &pool))
+            .collect();
+        let previous_best = hall_of_fame.first().map(|h| h.0);
+        for island in &ranked {
+            update_hall_of_fame(&mut hall_of_fame, island, hall_of_fame_size);
+        }
+        let best = hall_of_fame[0].0;
+        if previous_best.is_none_or(|previous| best > previous) {
+            improved = generation;
+        }
+
+        let everyone: Vec<&(f32, Squad)> = ranked.iter().flatten().collect();
+        let population_diversity = diversity(everyone.iter().map(|(_, team)| team.as_slice()));
+        let total: f32 = everyone.iter().map(|r| r.0).sum();
+        history.push(GenerationStats {
+            generation,
+            best_fitness: everyone.iter().map(|r| r.0).fold(f32::MIN, f32::max),
+            mean_fitness: total / everyone.len() as f32,
+            worst_fitness: everyone.iter().map(|r| r.0).fold(f32::MAX, f32::min),
+            diversity: population_diversity,
+            children: bred.children,
+            new_children: bred.new,
+            repaired_children: bred.repaired,
+            parent_copies: bred.failed,
+        });
+
+        if config.target_fitness.is_some_and(|target| best >= target) {
+            break StopReason::TargetReached;
+        }
+        if config
+            .patience
+            .is_some_and(|patience| generation - improved >= patience)
+        {
+            break StopReason::Stalled;
+        }
+        if config
+            .min_diversity
+            .is_some_and(|min| population_diversity < min)
+        {
+            break StopReason::Converged;
+        }
+        if config
+            .time_limit
+            .is_some_and(|limit| start.elapsed() >= limit)
+        {
+            break StopReason::TimeLimit;
+        }
+        if generation == config.generations {
+            break StopReason::Generations;
+        }
+
+        if ranked.len() > 1 && generation > 0 && generation % config.migration_interval.max(1) == 0
+        {
+            migrate(&mut ranked, config.migration_size);
+        }
+
+        // Each island breeds from its own generator on its own thread
+        let next: Vec<(Vec<Squad>, CrossoverStats, ChaCha8Rng)> = ranked
+            .into_par_iter()
+            .zip(rngs)
+            .map(|(island, mut island_rng)| {
+                let (population, stats) = breed(&island, &pool, &mut island_rng);
+                (population, stats, island_rng)
+            })
+            .collect();
+
+        bred = CrossoverStats::default();
+        islands = Vec::with_capacity(next.len());
+        for (population, island_stats, island_rng) in next {
+            bred.add(&island_stats);
+            stats.add(&island_stats);
+            islands.push((population, island_rng));
+        }
+        generation += 1;
+        progress_bar.inc(1);
+    };
+
+    progress_bar.finish_with_message(format!("Genetic algorithm stopped: {}", stop_reason));
+
+    let hall_of_fame: Vec<SelectionResult> = hall_of_fame
+        .into_iter()
+        .map(|(_, squad)| SelectionResult::new(pool.to_players(&squad), config))
+        .collect();
+    Ok(GaSolution {
+        result: hall_of_fame[0].clone(),
+        hall_of_fame,
+        crossover: stats,
+        generations: generation,
+        stop_reason,
+        history,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, check_squad};
+    use proptest::prelude::*;
+
+    // A pool of fixture players with random locks and exclusions
+    fn setup(seed: u64) -> (Vec<Player>, SelectionConfig, ChaCha8Rng) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let players = fixtures::players(150, seed);
+        let mut config = SelectionConfig {
+            population_size: 20,
+            generations: 10,
+            seed: Some(seed),
+            ..SelectionConfig::default()
+        };
+        fixtures::lock_and_exclude(&players, &mut config, &mut rng);
+        (players, config, rng)
+    }
+
+    #[test]
+    fn rejects_an_empty_population() {
+        let (players, mut config, _) = setup(1);
+        config.population_size = 0;
+        assert!(select_best_team_ga(&players, &config).is_err());
+    }
+
+    #[test]
+    fn breeds_a_child_however_large_the_elite() {
+        let (players, mut config, mut rng) = setup(1);
+        for population_size in 1..=3 {
+            config.population_size = population_size;
+            config.elite_count = 3;
+            let pool = Pool::new(&players, &config);
+            let ranked = rank(create_initial_population(&pool, &mut rng).unwrap(), &pool);
+            let (population, stats) = breed(&ranked, &pool, &mut rng);
+            assert_eq!(population.len(), population_size);
+            assert_eq!(stats.children, 1);
+        }
+    }
+
+    #[test]
+    fn seeded_runs_repeat_across_islands() {
+        let (players, mut config, _) = setup(7);
+        config.islands = 3;
+        config.migration_interval = 3;
+        // However many threads the islands and children are spread over
+        let run = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| select_best_team_ga(&players, &config).unwrap())
+        };
+        let first = run(1);
+        let second = run(4);
+        let elements = |solution: &GaSolution| -> Vec<u32> {
+            solution.result.squad.iter().map(|p| p.element).collect()
+        };
+        assert_eq!(elements(&first), elements(&second));
+        assert_eq!(first.history, second.history);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn random_teams_are_valid(seed: u64) {
+            let (players, config, mut rng) = setup(seed);
+            let team = generate_random_team(&players, &config, &mut rng).unwrap();
+            prop_assert_eq!(check_squad(&team, &config), Ok(()));
+        }
+
+        #[test]
+        fn crossover_children_are_valid(seed: u64) {
+            let (players, mut config, mut rng) = setup(seed);
+            let pool = Pool::new(&players, &config);
+            let parent1 = pool.random_squad(&mut rng).unwrap();
+            let parent2 = pool.random_squad(&mut rng).unwrap();
+            // Leave no spare budget, so that many children need a repair
+            config.max_value = pool.value(&parent1).max(pool.value(&parent2));
+            let pool = Pool::new(&players, &config);
+            let (child, _) = crossover(&parent1, &parent2, &pool, &mut rng);
+            prop_assert_eq!(check_squad(&pool.to_players(&child), &config), Ok(()));
+        }
+
+        #[test]
+        fn mutants_are_valid(seed: u64, mutation_rate in 0.0..=1.0f32) {
+            let (players, mut config, mut rng) = setup(seed);
+            config.mutation_rate = mutation_rate;
+            let pool = Pool::new(&players, &config);
+            let mut team = pool.random_squad(&mut rng).unwrap();
+            config.max_value = pool.value(&team);
+            let pool = Pool::new(&players, &config);
+            mutate(&mut team, &pool, &mut rng);
+            prop_assert_eq!(check_squad(&pool.to_players(&team), &config), Ok(()));
+        }
+
+        #[test]
+        fn bred_generations_are_valid(seed: u64, selection in 0..4usize) {
+            let (players, mut config, mut rng) = setup(seed);
+            config.selection = [
+                ParentSelection::Truncation,
+                ParentSelection::Tournament,
+                ParentSelection::Roulette,
+                ParentSelection::Rank,
+            ][selection];
+            let pool = Pool::new(&players, &config);
+            let ranked = rank(create_initial_population(&pool, &mut rng).unwrap(), &pool);
+            let (population, stats) = breed(&ranked, &pool, &mut rng);
+
+            prop_assert_eq!(population.len(), config.population_size);
+            prop_assert_eq!(stats.children as usize, config.population_size - config.elite_count);
+            for squad in &population {
+                prop_assert_eq!(check_squad(&pool.to_players(squad), &config), Ok(()));
+            }
+        }
+
+        #[test]
+        fn migrants_keep_islands_valid(seed: u64) {
+            let (players, config, mut rng) = setup(seed);
+            let pool = Pool::new(&players, &config);
+            let mut islands: Vec<Vec<(f32, Squad)>> = (0..3)
+                .map(|_| rank(create_initial_population(&pool, &mut rng).unwrap(), &pool))
+                .collect();
+            migrate(&mut islands, config.migration_size);
+            for island in &islands {
+                prop_assert_eq!(island.len(), config.population_size);
+                for (_, squad) in island {
+                    prop_assert_eq!(check_squad(&pool.to_players(squad), &config), Ok(()));
+                }
+            }
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn best_teams_are_valid(seed: u64, islands in 1..3usize) {
+            let (players, mut config, _) = setup(seed);
+            config.islands = islands;
+            let solution = select_best_team_ga(&players, &config).unwrap();
+            for result in &solution.hall_of_fame {
+                prop_assert_eq!(check_squad(&result.squad, &config), Ok(()));
+            }
+            prop_assert!(solution.history.len() <= config.generations + 1);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Fantasy football squad selection from predicted points.
+//!
+//! Players are read with [`read_csv`], optionally scored over several
+//! gameweeks with [`apply_window`], and a squad is chosen by the genetic
+//! algorithm in [`select_best_team_ga`], by simulated annealing in
+//! [`select_best_team_sa`], or proven optimal by [`select_best_team_exact`].
+//! All maximise [`fitness`] subject to [`satisfies_constraints`] under a
+//! [`SelectionConfig`], whose [`SquadRules`] default to Fantasy Premier
+//! League's or come from [`read_rules`], and report a [`SelectionResult`],
+//! which [`write_selection`] writes as text, a table, JSON or CSV.
+//! [`polish_team`] improves any squad by local search. [`plan_transfers`]
+//! instead plans weekly transfers for an existing squad over several
+//! gameweeks.
+
+mod anneal;
+mod exact;
+#[cfg(test)]
+mod fixtures;
+mod ga;
+mod output;
+mod planner;
+mod player;
+mod polish;
+mod pool;
+mod rules;
+mod squad;
+
+pub use anneal::{select_best_team_sa, AnnealConfig, AnnealSolution, CoolingSchedule};
+pub use exact::{select_best_team_exact, ExactSolution};
+pub use ga::{
+    select_best_team_ga, CrossoverStats, GaSolution, GenerationStats, ParentSelection, StopReason,
+};
+pub use output::{
+    write_history, write_selection, OutputFormat, ReportPlayer, ReportRow, SelectionReport,
+    SCHEMA_VERSION,
+};
+pub use planner::{plan_transfers, read_squad, GameweekPlan, PlanConfig, Transfer, TransferPlan};
+pub use player::{
+    apply_window, find_players, read_csv, read_player_list, read_predictions, replace_non_finite,
+    CsvConfig, GameweekWindow, InvalidRows, NanPolicy, Player, PlayerCsv, RowProblem, Validation,
+    ValidationError,
+};
+pub use polish::{polish_team, Improvement, PolishConfig, PolishSolution};
+pub use rules::{read_rules, PositionRule, RulesFile, SquadRules};
+pub use squad::{
+    check_feasibility, check_player_lists, fitness, satisfies_constraints, SelectionConfig,
+    SelectionResult, CAPTAIN_MULTIPLIER, TRIPLE_CAPTAIN_MULTIPLIER,
+};
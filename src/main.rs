@@ -1,269 +1,689 @@
-use indicatif::{ProgressBar, ProgressStyle};
-use rand::seq::{IteratorRandom, SliceRandom};
-use rand::Rng;
-use rayon::prelude::*;
-use serde::Deserialize;
-use std::collections::HashMap;
+use clap::builder::RangedU64ValueParser;
+use clap::{Args, Parser, Subcommand};
+use std::collections::HashSet;
 use std::error::Error;
 use std::fs::File;
-use std::sync::{Arc, Mutex};
-
-#[derive(Debug, Deserialize, Clone)]
-struct Player {
-    element: u32,
-    name: String,
-    value: f32,
-    position: String,
-    team: String,
-    predicted_points: f32,
+use std::time::Duration;
+use team_selector::{
+    apply_window, check_feasibility, find_players, fitness, plan_transfers, polish_team, read_csv,
+    read_player_list, read_predictions, read_rules, read_squad, select_best_team_exact,
+    select_best_team_ga, select_best_team_sa, write_history, write_selection, AnnealConfig,
+    CoolingSchedule, CsvConfig, GaSolution, GameweekWindow, NanPolicy, OutputFormat,
+    ParentSelection, PlanConfig, Player, PolishConfig, PolishSolution, RulesFile, SelectionConfig,
+    SelectionResult, SquadRules, TransferPlan, Validation, CAPTAIN_MULTIPLIER,
+    TRIPLE_CAPTAIN_MULTIPLIER,
+};
+
+fn print_plan(plan: &TransferPlan, config: &SelectionConfig) {
+    for week in &plan.gameweeks {
+        println!(
+            "Gameweek {} - Free Transfers: {}",
+            week.gameweek, week.free_transfers
+        );
+        if week.transfers.is_empty() {
+            println!("  No transfers");
+        }
+        for transfer in &week.transfers {
+            println!(
+                "  Out: {} ({}) - In: {} ({})",
+                transfer.sold.name,
+                transfer.sold.value,
+                transfer.bought.name,
+                transfer.bought.value
+            );
+        }
+        if week.hit > 0.0 {
+            println!("  Points hit: -{}", week.hit);
+        }
+        println!(
+            "  Formation: {} - Captain: {} - Vice-Captain: {} - Bank: {}",
+            week.result.formation(config),
+            week.result.captain.name,
+            week.result.vice_captain.name,
+            week.bank
+        );
+        println!(
+            "  Predicted Points (XI with captain x{}): {}",
+            config.captain_multiplier,
+            week.result.expected_points(config)
+        );
+    }
+    println!("Total plan fitness after hits: {}", plan.total_score);
+}
+
+#[derive(Parser)]
+#[command(about = "Select a fantasy football squad from predicted points")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Evolve a squad with the genetic algorithm
+    Ga {
+        #[command(flatten)]
+        squad: SquadArgs,
+        #[command(flatten)]
+        ga: GaArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Anneal a squad from a random one with simulated annealing
+    Anneal {
+        #[command(flatten)]
+        squad: SquadArgs,
+        #[command(flatten)]
+        anneal: AnnealArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Find the proven optimal squad with branch-and-bound
+    Exact {
+        #[command(flatten)]
+        squad: SquadArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Run both optimisers and report the genetic algorithm's optimality gap
+    Compare {
+        #[command(flatten)]
+        squad: SquadArgs,
+        #[command(flatten)]
+        ga: GaArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+    },
+    /// Plan weekly transfers for an existing squad over several gameweeks with
+    /// a beam search, a heuristic that may miss the best plan
+    Plan(PlanArgs),
 }
 
-fn read_csv(path: &str) -> Result<Vec<Player>, Box<dyn Error>> {
-    let file = File::open(path)?;
-    let mut rdr = csv::Reader::from_reader(file);
-    let mut players = Vec::new();
+#[derive(Args)]
+struct InputArgs {
+    /// CSV file of players, their values and predicted points
+    #[arg(short, long, default_value = "./df_encoded_new.csv")]
+    input: String,
+    /// Skip rows of the input that fail validation instead of stopping
+    #[arg(long)]
+    lenient: bool,
+    /// What to do with NaN or infinite predicted points: reject, zero or
+    /// impute from the averages of the player's position
+    #[arg(long, default_value_t = NanPolicy::default())]
+    nan: NanPolicy,
+    /// TOML or JSON file of squad rules: positions and their aliases, squad
+    /// and starting XI counts, bench size, budget and club cap. Fantasy
+    /// Premier League rules by default
+    #[arg(long)]
+    rules: Option<String>,
+    /// Maximum players selected from any one club; by default the rules
+    /// file's, or 3
+    #[arg(long)]
+    club_cap: Option<usize>,
+}
 
-    for result in rdr.deserialize() {
-        let player: Player = result?;
-        players.push(player);
+#[derive(Args)]
+struct SquadArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Total squad budget, in the same units as the value column; by default
+    /// the rules file's, or 1000
+    #[arg(long)]
+    budget: Option<f32>,
+    /// Use the triple-captain multiplier instead of the usual double points
+    #[arg(long)]
+    triple_captain: bool,
+    /// Score players over this many gameweeks of their per-gameweek predictions
+    #[arg(long)]
+    gameweeks: Option<usize>,
+    /// First gameweek of the window, by default the earliest one predicted
+    #[arg(long)]
+    first_gameweek: Option<u32>,
+    /// Weight of each gameweek in the window relative to the one before
+    #[arg(long, default_value_t = 1.0)]
+    discount: f32,
+    /// Comma-separated weights for each gameweek of the window, overriding
+    /// --gameweeks and --discount
+    #[arg(long, value_delimiter = ',')]
+    weights: Option<Vec<f32>>,
+    /// Comma-separated element ids or names of players the squad must include
+    #[arg(long, value_delimiter = ',')]
+    lock: Vec<String>,
+    /// File of players the squad must include, one element id or name per line
+    #[arg(long)]
+    lock_file: Option<String>,
+    /// Comma-separated element ids or names of players the squad must not include
+    #[arg(long, value_delimiter = ',')]
+    exclude: Vec<String>,
+    /// File of players the squad must not include, one element id or name per line
+    #[arg(long)]
+    exclude_file: Option<String>,
+}
+
+// The rules of the file given, or the default rules
+fn rules_file(path: &Option<String>) -> Result<RulesFile, Box<dyn Error>> {
+    match path {
+        Some(path) => read_rules(path),
+        None => Ok(RulesFile::default()),
     }
+}
 
-    Ok(players)
+// Read the players, reporting any rows skipped or points replaced
+fn read_players(
+    path: &str,
+    lenient: bool,
+    nan_policy: NanPolicy,
+    rules: &SquadRules,
+) -> Result<Vec<Player>, Box<dyn Error>> {
+    let config = CsvConfig {
+        validation: if lenient {
+            Validation::Lenient
+        } else {
+            Validation::Strict
+        },
+        nan_policy,
+        rules: rules.clone(),
+    };
+    let csv = read_csv(path, &config)?;
+    if csv.skipped_rows > 0 || csv.replaced_points > 0 {
+        eprintln!("{}", csv.summary());
+    }
+    Ok(csv.players)
 }
 
-// Genetic Algorithm parameters
-const POPULATION_SIZE: usize = 150;
-const GENERATIONS: usize = 2500;
-const MUTATION_RATE: f32 = 0.1;
-const MAX_VALUE: f32 = 1000.0;
-const MAX_PLAYERS_PER_TEAM: usize = 3;
-
-// Constraints for positions
-fn max_positions() -> HashMap<String, usize> {
-    HashMap::from([
-        ("GK".to_string(), 2),
-        ("DEF".to_string(), 5),
-        ("MID".to_string(), 5),
-        ("FWD".to_string(), 3),
-    ])
+// Element ids of the players named on the command line and in the file
+fn player_list(
+    players: &[Player],
+    names: &[String],
+    file: &Option<String>,
+) -> Result<HashSet<u32>, Box<dyn Error>> {
+    let mut names = names.to_vec();
+    if let Some(path) = file {
+        names.extend(read_player_list(path)?);
+    }
+    find_players(players, &names)
 }
 
-// Fitness function
-fn fitness(team: &Vec<Player>) -> f32 {
-    let total_value: f32 = team.iter().map(|p| p.value).sum();
-    if total_value > MAX_VALUE {
-        return 0.0;
+#[derive(Args)]
+struct GaArgs {
+    /// Number of squads in each generation of each island
+    #[arg(
+        long,
+        default_value_t = SelectionConfig::default().population_size,
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+    )]
+    population: usize,
+    /// Number of generations to evolve
+    #[arg(long, default_value_t = SelectionConfig::default().generations)]
+    generations: usize,
+    /// Probability that each player of a child squad is swapped out
+    #[arg(long, default_value_t = SelectionConfig::default().mutation_rate)]
+    mutation_rate: f32,
+    /// How parents are picked: truncation, tournament, roulette or rank
+    #[arg(long, default_value_t = SelectionConfig::default().selection)]
+    selection: ParentSelection,
+    /// Squads drawn for each tournament with --selection tournament
+    #[arg(long, default_value_t = SelectionConfig::default().tournament_size)]
+    tournament_size: usize,
+    /// Best squads of each generation carried into the next unchanged, at
+    /// most one fewer than the population
+    #[arg(long, default_value_t = SelectionConfig::default().elite_count)]
+    elite: usize,
+    /// Distinct best squads kept in the hall of fame and reported
+    #[arg(long, default_value_t = SelectionConfig::default().hall_of_fame_size)]
+    hall_of_fame: usize,
+    /// Populations evolving side by side on separate threads
+    #[arg(long, default_value_t = SelectionConfig::default().islands)]
+    islands: usize,
+    /// Generations between migrations from each island to the next
+    #[arg(long, default_value_t = SelectionConfig::default().migration_interval)]
+    migration_interval: usize,
+    /// Best squads of each island copied to the next at a migration
+    #[arg(long, default_value_t = SelectionConfig::default().migration_size)]
+    migration_size: usize,
+    /// Stop once the best fitness has not improved for this many generations
+    #[arg(long)]
+    patience: Option<usize>,
+    /// Stop once a squad reaches this fitness
+    #[arg(long)]
+    target_fitness: Option<f32>,
+    /// Stop after this many seconds
+    #[arg(long, value_parser = parse_seconds)]
+    time_limit: Option<Duration>,
+    /// Stop once the share of distinct squads in the population falls below
+    /// this, between 0 and 1
+    #[arg(long)]
+    min_diversity: Option<f32>,
+    /// Return the genetic algorithm's best squad without polishing it by
+    /// local search
+    #[arg(long)]
+    no_polish: bool,
+    /// Swaps in each tabu walk of the local search
+    #[arg(long, default_value_t = PolishConfig::default().tabu_moves)]
+    tabu_moves: usize,
+    /// Swaps for which players moved in a tabu walk cannot move back
+    #[arg(long, default_value_t = PolishConfig::default().tabu_tenure)]
+    tabu_tenure: usize,
+    /// Write each generation's fitness, diversity and crossover counts to
+    /// this file
+    #[arg(long)]
+    history: Option<String>,
+    /// Format of the --history file: csv, json, table or text
+    #[arg(long, default_value_t = OutputFormat::Csv)]
+    history_format: OutputFormat,
+    #[command(flatten)]
+    seed: SeedArgs,
+}
+
+#[derive(Args)]
+struct SeedArgs {
+    /// Seed for the random numbers, so that a run can be repeated exactly; a
+    /// random seed is chosen and reported if none is given
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+impl SeedArgs {
+    // The seed given, or a random one to report
+    fn seed(&self) -> u64 {
+        self.seed.unwrap_or_else(rand::random)
     }
-    let mut points: Vec<_> = team.iter().map(|p| p.predicted_points).collect();
+}
 
-    // Sort using partial_cmp and unwrap to handle floating-point comparisons
-    points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+// A non-negative, finite number of seconds
+fn parse_seconds(s: &str) -> Result<Duration, String> {
+    let seconds: f64 = s
+        .parse()
+        .map_err(|err: std::num::ParseFloatError| err.to_string())?;
+    Duration::try_from_secs_f64(seconds).map_err(|_| {
+        format!(
+            "{} is not a non-negative number of seconds that fits a duration",
+            s
+        )
+    })
+}
 
-    let adjusted_sum: f32 = points
-        .iter()
-        .enumerate()
-        .map(|(i, &pts)| if i < 4 { pts * 0.25 } else { pts })
-        .sum();
+#[derive(Args)]
+struct AnnealArgs {
+    /// Moves to try
+    #[arg(long, default_value_t = AnnealConfig::default().iterations)]
+    iterations: u64,
+    /// Temperature of the first move, in fitness points
+    #[arg(long, default_value_t = AnnealConfig::default().initial_temperature)]
+    initial_temperature: f32,
+    /// Temperature of the last move
+    #[arg(long, default_value_t = AnnealConfig::default().final_temperature)]
+    final_temperature: f32,
+    /// How the temperature falls: geometric or linear
+    #[arg(long, default_value_t = AnnealConfig::default().schedule)]
+    cooling: CoolingSchedule,
+    #[command(flatten)]
+    seed: SeedArgs,
+}
+
+#[derive(Args)]
+struct OutputArgs {
+    /// Output format: text, table, json or csv
+    #[arg(long, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    /// Write the selection to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<String>,
+}
 
-    adjusted_sum
+#[derive(Args)]
+struct PlanArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// CSV file of element,gameweek,points predictions, if they are not
+    /// already in the input
+    #[arg(long)]
+    predictions: Option<String>,
+    /// CSV file with an element column listing the current squad
+    #[arg(long)]
+    current_squad: String,
+    /// Money in the bank, in the same units as the value column; the budget is
+    /// the current squad's value plus the bank, whatever the rules file says
+    #[arg(long, default_value_t = 0.0)]
+    bank: f32,
+    /// Free transfers available for the first gameweek
+    #[arg(long, default_value_t = PlanConfig::default().free_transfers)]
+    free_transfers: usize,
+    /// Transfers beyond the free ones allowed each week, each costing a
+    /// points hit
+    #[arg(long, default_value_t = PlanConfig::default().max_paid_transfers)]
+    max_paid_transfers: usize,
+    /// First gameweek to plan, by default the earliest one predicted
+    #[arg(long)]
+    first_gameweek: Option<u32>,
+    /// Number of gameweeks to plan
+    #[arg(long, default_value_t = PlanConfig::default().horizon)]
+    horizon: usize,
+    /// Plans kept after each gameweek of the search; wider beams search more
+    /// plans but take longer
+    #[arg(long, default_value_t = PlanConfig::default().beam_width)]
+    beam_width: usize,
 }
 
-// Check team constraints
-fn satisfies_constraints(team: &Vec<Player>) -> bool {
-    let mut team_counts = HashMap::new();
-    let mut position_counts = HashMap::new();
-    let mut total_value = 0.0;
+impl InputArgs {
+    // Read the rules file, or the default rules, and the players under them
+    fn read(&self) -> Result<(RulesFile, Vec<Player>), Box<dyn Error>> {
+        let file = rules_file(&self.rules)?;
+        let players = read_players(&self.input, self.lenient, self.nan, &file.rules)?;
+        Ok((file, players))
+    }
 
-    let mut unique_elements = HashMap::new();
-    for player in team {
-        if unique_elements.contains_key(&player.element) {
-            return false;
+    // The club cap given, else the rules file's, else the default
+    fn club_cap(&self, file: &RulesFile) -> usize {
+        self.club_cap
+            .or(file.club_cap)
+            .unwrap_or(SelectionConfig::default().max_players_per_team)
+    }
+}
+
+impl SquadArgs {
+    // Command-line limits override the rules file's
+    fn config(
+        &self,
+        players: &[Player],
+        file: RulesFile,
+    ) -> Result<SelectionConfig, Box<dyn Error>> {
+        if let Some(budget) = self.budget.filter(|b| !b.is_finite() || *b < 0.0) {
+            return Err(format!("The budget of {} is not a non-negative number", budget).into());
         }
-        unique_elements.insert(player.element, true);
+        let defaults = SelectionConfig::default();
+        let config = SelectionConfig {
+            max_value: self.budget.or(file.budget).unwrap_or(defaults.max_value),
+            max_players_per_team: self.input.club_cap(&file),
+            rules: file.rules,
+            captain_multiplier: if self.triple_captain {
+                TRIPLE_CAPTAIN_MULTIPLIER
+            } else {
+                CAPTAIN_MULTIPLIER
+            },
+            locked: player_list(players, &self.lock, &self.lock_file)?,
+            excluded: player_list(players, &self.exclude, &self.exclude_file)?,
+            progress: true,
+            ..SelectionConfig::default()
+        };
+        check_feasibility(players, &config)?;
+        Ok(config)
     }
 
-    for player in team {
-        *position_counts.entry(player.position.clone()).or_insert(0) += 1;
-        *team_counts.entry(player.team.clone()).or_insert(0) += 1;
-        total_value += player.value;
+    // Read the rules and the players, scoring them over the gameweek window
+    // if one is set, and build the squad rules
+    fn load(&self) -> Result<(Vec<Player>, SelectionConfig), Box<dyn Error>> {
+        let (file, players) = self.input.read()?;
+        let players = self.apply_window(players)?;
+        let config = self.config(&players, file)?;
+        Ok((players, config))
+    }
 
-        if let Some(&max_pos) = max_positions().get(&player.position) {
-            if position_counts[&player.position] > max_pos {
-                return false;
-            }
+    fn apply_window(&self, mut players: Vec<Player>) -> Result<Vec<Player>, Box<dyn Error>> {
+        if self.gameweeks.is_none() && self.weights.is_none() {
+            return Ok(players);
+        }
+        if !self.discount.is_finite() {
+            return Err(format!("The discount of {} is not a finite number", self.discount).into());
         }
-        if team_counts[&player.team] > MAX_PLAYERS_PER_TEAM || total_value > MAX_VALUE {
-            return false;
+        if let Some(weight) = self.weights.iter().flatten().find(|w| !w.is_finite()) {
+            return Err(format!("The weight of {} is not a finite number", weight).into());
         }
+
+        let first_gameweek = match self.first_gameweek {
+            Some(gw) => gw,
+            None => players
+                .iter()
+                .flat_map(|p| p.gameweek_points.keys().copied())
+                .min()
+                .ok_or("The input has no per-gameweek predictions")?,
+        };
+        let window = match &self.weights {
+            Some(weights) => GameweekWindow {
+                first_gameweek,
+                weights: weights.clone(),
+            },
+            None => GameweekWindow::discounted(
+                first_gameweek,
+                self.gameweeks.unwrap_or(1),
+                self.discount,
+            ),
+        };
+        apply_window(&mut players, &window);
+        Ok(players)
     }
-    team.len() == 15
 }
 
-// Generate a random team satisfying constraints
-fn generate_random_team(players: &Vec<Player>) -> Vec<Player> {
-    let mut rng = rand::thread_rng();
-    let mut team = Vec::new();
-    let mut team_counts = HashMap::new();
-    let mut position_counts = HashMap::new();
-    let mut total_value = 0.0;
-
-    while team.len() < 15 {
-        if let Some(player) = players.choose(&mut rng) {
-            let position_count = position_counts.entry(player.position.clone()).or_insert(0);
-            let team_count = team_counts.entry(player.team.clone()).or_insert(0);
-
-            if *position_count < *max_positions().get(&player.position).unwrap_or(&0)
-                && *team_count < MAX_PLAYERS_PER_TEAM
-                && total_value + player.value <= MAX_VALUE
-            {
-                team.push(player.clone());
-                *position_count += 1;
-                *team_count += 1;
-                total_value += player.value;
-            }
+impl PlanArgs {
+    fn config(&self) -> PlanConfig {
+        PlanConfig {
+            first_gameweek: self.first_gameweek,
+            horizon: self.horizon,
+            bank: self.bank,
+            free_transfers: self.free_transfers,
+            max_paid_transfers: self.max_paid_transfers,
+            beam_width: self.beam_width,
+            ..PlanConfig::default()
         }
     }
-    team
 }
 
-// Initialize population
-fn create_initial_population(players: &Vec<Player>) -> Vec<Vec<Player>> {
-    (0..POPULATION_SIZE)
-        .map(|_| generate_random_team(players))
-        .collect()
-}
-
-// Perform crossover while maintaining constraints
-fn crossover(parent1: &Vec<Player>, parent2: &Vec<Player>) -> Vec<Player> {
-    
-    let mut rng = rand::thread_rng();
-    let split = rng.gen_range(0..15);
-    let mut child = Vec::new();
-
-    child.extend_from_slice(&parent1[..split]);
-    child.extend_from_slice(&parent2[split..]);
-
-    // Ensure constraints, remove duplicates, add unique players if necessary
-   
-    child.dedup_by_key(|p| p.element);
-    let mut cur = 0;
-    while child.len() < 15 && cur < 400 {
-        cur += 1;
-        if let Some(player) = parent1.choose(&mut rng) {
-            if !child.iter().any(|p| p.element == player.element) {
-                child.push(player.clone());
-            }
+impl OutputArgs {
+    fn write(
+        &self,
+        result: &SelectionResult,
+        config: &SelectionConfig,
+    ) -> Result<(), Box<dyn Error>> {
+        match &self.output {
+            Some(path) => write_selection(&mut File::create(path)?, result, config, self.format),
+            None => write_selection(&mut std::io::stdout().lock(), result, config, self.format),
         }
     }
 
-    // Final check to ensure constraints are satisfied
-    if satisfies_constraints(&child) {
-        child
-    } else {
-        if rng.gen::<f32>() < 0.5 {
-            parent1.clone()
+    // Extra summary lines go to stderr when stdout carries JSON or CSV
+    fn note(&self, message: String) {
+        let structured = matches!(self.format, OutputFormat::Json | OutputFormat::Csv);
+        if structured && self.output.is_none() {
+            eprintln!("{}", message);
         } else {
-            parent2.clone()
+            println!("{}", message);
         }
     }
-}
 
-// Mutation with constraint check
-fn mutate(team: &mut Vec<Player>, players: &Vec<Player>) {
-    let mut rng = rand::thread_rng();
-    if rng.gen::<f32>() < MUTATION_RATE {
-        if let Some(index) = (0..team.len()).choose(&mut rng) {
-            if let Some(new_player) = players.choose(&mut rng) {
-                team[index] = new_player.clone();
+    fn note_ga(&self, solution: &GaSolution, config: &SelectionConfig) {
+        let stats = &solution.crossover;
+        self.note(format!("Seed: {}", config.seed.unwrap_or_default()));
+        self.note(format!(
+            "Stopped after {} generations: {}",
+            solution.generations, solution.stop_reason
+        ));
+        self.note(format!(
+            "Crossover: {} of {} children new ({:.1}%), {} repaired, {} replaced by a parent",
+            stats.new,
+            stats.children,
+            100.0 * stats.new_rate(),
+            stats.repaired,
+            stats.failed
+        ));
+        let scores: Vec<String> = solution
+            .hall_of_fame
+            .iter()
+            .map(|r| r.score.to_string())
+            .collect();
+        self.note(format!("Hall of fame fitness: {}", scores.join(", ")));
+    }
+
+    fn note_polish(&self, polished: &PolishSolution) {
+        for improvement in &polished.improvements {
+            self.note(format!(
+                "Local search: {} -> {}",
+                improvement.before, improvement.after
+            ));
+            for transfer in &improvement.transfers {
+                self.note(format!(
+                    "  Out: {} - In: {}",
+                    transfer.sold.name, transfer.bought.name
+                ));
             }
         }
     }
+}
 
-    if !satisfies_constraints(team) {
-        *team = generate_random_team(players);
+impl AnnealArgs {
+    fn config(&self) -> AnnealConfig {
+        AnnealConfig {
+            iterations: self.iterations,
+            initial_temperature: self.initial_temperature,
+            final_temperature: self.final_temperature,
+            schedule: self.cooling,
+        }
     }
 }
 
-// Genetic algorithm main function
-fn select_best_team_ga(players: Vec<Player>) -> Vec<Player> {
-    let mut population = create_initial_population(&players);
-
-    let progress_bar = ProgressBar::new(GENERATIONS as u64);
-    progress_bar.set_style(
-        ProgressStyle::default_bar().template(
-            "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
-        ),
-    );
-
-    for g in 0..GENERATIONS {
-       
-        // Evaluate fitness in parallel
-        let fitness_scores: Vec<(f32, Vec<Player>)> = population
-            .par_iter()
-            .map(|team| (fitness(team), team.clone()))
-            .collect();
-
-        // Sort by fitness and select top half
-        let mut best_individuals = fitness_scores.clone();
-        best_individuals.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
-        best_individuals.truncate(POPULATION_SIZE / 2);
-
-        // Generate new population with crossover and mutation
-        let mut new_population = Vec::new();
-        while new_population.len() < POPULATION_SIZE {
-            let parent1 = &best_individuals.choose(&mut rand::thread_rng()).unwrap().1;
-            let parent2 = &best_individuals.choose(&mut rand::thread_rng()).unwrap().1;
-            let mut child = crossover(parent1, parent2);
-            mutate(&mut child, &players);
-            new_population.push(child);
+impl GaArgs {
+    // Polish the genetic algorithm's squad unless --no-polish is given
+    fn polish(
+        &self,
+        solution: &GaSolution,
+        players: &[Player],
+        config: &SelectionConfig,
+    ) -> PolishSolution {
+        if self.no_polish {
+            return PolishSolution {
+                result: solution.result.clone(),
+                improvements: Vec::new(),
+            };
         }
+        let polish = PolishConfig {
+            tabu_moves: self.tabu_moves,
+            tabu_tenure: self.tabu_tenure,
+        };
+        polish_team(&solution.result.squad, players, config, &polish)
+    }
 
-        population = new_population;
-        progress_bar.inc(1);
+    fn write_history(&self, solution: &GaSolution) -> Result<(), Box<dyn Error>> {
+        match &self.history {
+            Some(path) => write_history(
+                &mut File::create(path)?,
+                &solution.history,
+                self.history_format,
+            ),
+            None => Ok(()),
+        }
     }
 
-    progress_bar.finish_with_message("Genetic algorithm complete!");
+    fn apply(&self, config: SelectionConfig) -> SelectionConfig {
+        SelectionConfig {
+            population_size: self.population,
+            generations: self.generations,
+            mutation_rate: self.mutation_rate,
+            selection: self.selection,
+            tournament_size: self.tournament_size,
+            elite_count: self.elite,
+            hall_of_fame_size: self.hall_of_fame,
+            islands: self.islands,
+            migration_interval: self.migration_interval,
+            migration_size: self.migration_size,
+            patience: self.patience,
+            target_fitness: self.target_fitness,
+            time_limit: self.time_limit,
+            min_diversity: self.min_diversity,
+            seed: Some(self.seed.seed()),
+            ..config
+        }
+    }
+}
 
-    // Return the best team from the final population
-    population
-        .into_iter()
-        .max_by(|a, b| fitness(a).partial_cmp(&fitness(b)).unwrap())
-        .unwrap()
+// Fitness of a squad summed in order of element id, so that the same squad
+// scores the same however an optimiser ordered it
+fn canonical_fitness(squad: &[Player], config: &SelectionConfig) -> f32 {
+    let mut squad = squad.to_vec();
+    squad.sort_by_key(|p| p.element);
+    fitness(&squad, config)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let players = read_csv("./df_encoded_new.csv")?;
-    let best_team = select_best_team_ga(players);
-
-    let mut sorted_team = best_team.clone();
-    sorted_team.sort_by(|a, b| a.predicted_points.partial_cmp(&b.predicted_points).unwrap());
-
-    for (i, player) in best_team.iter().enumerate() {
-        if sorted_team.iter().position(|p| p.element == player.element).unwrap() < 4 {
-            println!(
-                "Selected: {} - Position: {} - Team: {} - Predicted Points: {} - Value: {} (Bench)",
-                player.name, player.position, player.team, player.predicted_points, player.value
-            );
-        } else {
-            println!(
-                "Selected: {} - Position: {} - Team: {} - Predicted Points: {} - Value: {}",
-                player.name, player.position, player.team, player.predicted_points, player.value
-            );
+    match Cli::parse().command {
+        Command::Ga { squad, ga, output } => {
+            let (players, config) = squad.load()?;
+            let config = ga.apply(config);
+            let solution = select_best_team_ga(&players, &config)?;
+            ga.write_history(&solution)?;
+            let polished = ga.polish(&solution, &players, &config);
+            output.write(&polished.result, &config)?;
+            output.note_ga(&solution, &config);
+            output.note_polish(&polished);
+        }
+        Command::Anneal {
+            squad,
+            anneal,
+            output,
+        } => {
+            let (players, config) = squad.load()?;
+            let config = SelectionConfig {
+                seed: Some(anneal.seed.seed()),
+                ..config
+            };
+            let solution = select_best_team_sa(&players, &config, &anneal.config())?;
+            output.write(&solution.result, &config)?;
+            output.note(format!("Seed: {}", config.seed.unwrap_or_default()));
+            output.note(format!(
+                "Accepted {} of {} moves, best squad found at move {}",
+                solution.accepted, anneal.iterations, solution.best_iteration
+            ));
+        }
+        Command::Exact { squad, output } => {
+            let (players, config) = squad.load()?;
+            let solution =
+                select_best_team_exact(&players, None, &config).ok_or("No valid squad exists")?;
+            output.write(&solution.result, &config)?;
+            output.note(format!(
+                "Proven optimal fitness: {} ({} nodes)",
+                solution.result.score, solution.nodes
+            ));
+        }
+        Command::Compare { squad, ga, output } => {
+            let (players, config) = squad.load()?;
+            let config = ga.apply(config);
+            let ga_solution = select_best_team_ga(&players, &config)?;
+            ga.write_history(&ga_solution)?;
+            let polished = ga.polish(&ga_solution, &players, &config);
+            let ga_score = ga_solution.result.score;
+            let solution = select_best_team_exact(&players, Some(&polished.result.squad), &config)
+                .ok_or("No valid squad exists")?;
+            output.write(&solution.result, &config)?;
+            output.note(format!(
+                "Proven optimal fitness: {} ({} nodes)",
+                solution.result.score, solution.nodes
+            ));
+            output.note(format!("GA fitness: {}", ga_score));
+            output.note_ga(&ga_solution, &config);
+            output.note_polish(&polished);
+            if !ga.no_polish {
+                output.note(format!("Polished fitness: {}", polished.result.score));
+            }
+            let optimum = canonical_fitness(&solution.result.squad, &config);
+            let gap = optimum - canonical_fitness(&ga_solution.result.squad, &config);
+            output.note(format!(
+                "Optimality gap: {} ({:.2}%)",
+                gap,
+                100.0 * gap / optimum
+            ));
+        }
+        Command::Plan(plan) => {
+            let (file, mut players) = plan.input.read()?;
+            let config = SelectionConfig {
+                max_players_per_team: plan.input.club_cap(&file),
+                rules: file.rules,
+                ..SelectionConfig::default()
+            };
+            if let Some(path) = &plan.predictions {
+                read_predictions(path, &mut players, plan.input.nan)?;
+            }
+            let current_squad = read_squad(&plan.current_squad)?;
+            let transfer_plan = plan_transfers(&players, &current_squad, &config, &plan.config())?;
+            print_plan(&transfer_plan, &config);
         }
     }
-
-    let (total_sum, max_point) = best_team.iter()
-        .map(|p| p.predicted_points)
-        .fold((0.0_f32, 0.0_f32), |(sum, max), x| (sum + x, max.max(x)));
-    
-    let total_predicted_points = total_sum + max_point;
-
-println!("Total Predicted Points (with highest doubled): {}", total_predicted_points);
-
-
-    
-
-    let total_value: f32 = best_team.iter().map(|p| p.value).sum();
-    println!("Total Value: {}", total_value);
     Ok(())
 }
@@ -0,0 +1,388 @@
+use crate::ga::GenerationStats;
+use crate::player::Player;
+use crate::squad::{SelectionConfig, SelectionResult};
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Version of the JSON layout written by [`write_selection`].
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// How a selection is written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One "Selected: ..." line per player followed by the totals.
+    Text,
+    /// An aligned table of the squad followed by the totals.
+    Table,
+    /// A [`SelectionReport`] object.
+    Json,
+    /// One [`ReportRow`] per squad member, starting XI first.
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!(
+                "Unknown output format '{}', expected text, table, json or csv",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A squad member as written in reports.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportPlayer {
+    pub element: u32,
+    pub name: String,
+    pub position: String,
+    pub team: String,
+    pub value: f32,
+    pub predicted_points: f32,
+}
+
+impl From<&Player> for ReportPlayer {
+    fn from(player: &Player) -> Self {
+        ReportPlayer {
+            element: player.element,
+            name: player.name.clone(),
+            position: player.position.clone(),
+            team: player.team.clone(),
+            value: player.value,
+            predicted_points: player.predicted_points,
+        }
+    }
+}
+
+/// The JSON document describing a selection:
+///
+/// ```json
+/// {
+///   "schema_version": 1,
+///   "formation": "3-4-3",
+///   "captain": 536,
+///   "vice_captain": 396,
+///   "captain_multiplier": 2.0,
+///   "total_value": 961.0,
+///   "expected_points": 76.05,
+///   "fitness": 81.21,
+///   "starting_xi": [
+///     {"element": 68, "name": "...", "position": "GK", "team": "MCI",
+///      "value": 59.0, "predicted_points": 5.36}
+///   ],
+///   "bench": []
+/// }
+/// ```
+///
+/// `captain` and `vice_captain` are `element` ids of starting XI players,
+/// `starting_xi` follows the order of the positions in the squad rules, and
+/// `bench` is in substitution order. `expected_points` counts the starting XI
+/// with the captain's multiplier; `fitness` is the optimisers' objective.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectionReport {
+    pub schema_version: u32,
+    pub formation: String,
+    pub captain: u32,
+    pub vice_captain: u32,
+    pub captain_multiplier: f32,
+    pub total_value: f32,
+    pub expected_points: f32,
+    pub fitness: f32,
+    pub starting_xi: Vec<ReportPlayer>,
+    pub bench: Vec<ReportPlayer>,
+}
+
+impl SelectionReport {
+    pub fn new(result: &SelectionResult, config: &SelectionConfig) -> Self {
+        SelectionReport {
+            schema_version: SCHEMA_VERSION,
+            formation: result.formation(config),
+            captain: result.captain.element,
+            vice_captain: result.vice_captain.element,
+            captain_multiplier: config.captain_multiplier,
+            total_value: result.total_value(),
+            expected_points: result.expected_points(config),
+            fitness: result.score,
+            starting_xi: result.starting_xi.iter().map(ReportPlayer::from).collect(),
+            bench: result.bench.iter().map(ReportPlayer::from).collect(),
+        }
+    }
+}
+
+/// A squad member as one CSV row. `role` is `captain`, `vice_captain`,
+/// `starter` or `bench`, and `bench_order` counts substitutes from 1.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportRow {
+    pub element: u32,
+    pub name: String,
+    pub position: String,
+    pub team: String,
+    pub value: f32,
+    pub predicted_points: f32,
+    pub role: &'static str,
+    pub bench_order: Option<usize>,
+}
+
+impl ReportRow {
+    fn new(player: &Player, role: &'static str, bench_order: Option<usize>) -> Self {
+        ReportRow {
+            element: player.element,
+            name: player.name.clone(),
+            position: player.position.clone(),
+            team: player.team.clone(),
+            value: player.value,
+            predicted_points: player.predicted_points,
+            role,
+            bench_order,
+        }
+    }
+}
+
+fn role(result: &SelectionResult, player: &Player) -> &'static str {
+    if player.element == result.captain.element {
+        "captain"
+    } else if player.element == result.vice_captain.element {
+        "vice_captain"
+    } else if result.bench.iter().any(|p| p.element == player.element) {
+        "bench"
+    } else {
+        "starter"
+    }
+}
+
+/// Write a selection to `out` in the given format.
+pub fn write_selection(
+    out: &mut dyn Write,
+    result: &SelectionResult,
+    config: &SelectionConfig,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Text => write_text(out, result, config)?,
+        OutputFormat::Table => write_table(out, result, config)?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, &SelectionReport::new(result, config))?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            for player in &result.starting_xi {
+                writer.serialize(ReportRow::new(player, role(result, player), None))?;
+            }
+            for (i, player) in result.bench.iter().enumerate() {
+                writer.serialize(ReportRow::new(player, "bench", Some(i + 1)))?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a genetic algorithm run's per-generation history to `out`: a JSON
+/// array or CSV rows of [`GenerationStats`], or an aligned table for text.
+pub fn write_history(
+    out: &mut dyn Write,
+    history: &[GenerationStats],
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Text | OutputFormat::Table => {
+            writeln!(
+                out,
+                "{:>10} {:>9} {:>9} {:>9} {:>9} {:>8} {:>8} {:>8} {:>7}",
+                "Generation",
+                "Best",
+                "Mean",
+                "Worst",
+                "Diversity",
+                "Children",
+                "New",
+                "Repaired",
+                "Copies"
+            )?;
+            for row in history {
+                writeln!(
+                    out,
+                    "{:>10} {:>9.2} {:>9.2} {:>9.2} {:>9.3} {:>8} {:>8} {:>8} {:>7}",
+                    row.generation,
+                    row.best_fitness,
+                    row.mean_fitness,
+                    row.worst_fitness,
+                    row.diversity,
+                    row.children,
+                    row.new_children,
+                    row.repaired_children,
+                    row.parent_copies
+                )?;
+            }
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, history)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            for row in history {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn write_totals(
+    out: &mut dyn Write,
+    result: &SelectionResult,
+    config: &SelectionConfig,
+) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "Total Predicted Points (XI with captain x{}): {}",
+        config.captain_multiplier,
+        result.expected_points(config)
+    )?;
+    writeln!(out, "Total Value: {}", result.total_value())
+}
+
+fn write_text(
+    out: &mut dyn Write,
+    result: &SelectionResult,
+    config: &SelectionConfig,
+) -> std::io::Result<()> {
+    writeln!(out, "Formation: {}", result.formation(config))?;
+
+    for player in result.starting_xi.iter().chain(&result.bench) {
+        let role = match role(result, player) {
+            "captain" => " (C)",
+            "vice_captain" => " (VC)",
+            "bench" => " (Bench)",
+            _ => "",
+        };
+        writeln!(
+            out,
+            "Selected: {} - Position: {} - Team: {} - Predicted Points: {} - Value: {}{}",
+            player.name, player.position, player.team, player.predicted_points, player.value, role
+        )?;
+    }
+
+    write_totals(out, result, config)
+}
+
+fn write_table(
+    out: &mut dyn Write,
+    result: &SelectionResult,
+    config: &SelectionConfig,
+) -> std::io::Result<()> {
+    let name_width = result
+        .squad
+        .iter()
+        .map(|p| p.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Player".len());
+    let team_width = result
+        .squad
+        .iter()
+        .map(|p| p.team.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Team".len());
+    let position_width = result
+        .squad
+        .iter()
+        .map(|p| p.position.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Pos".len() + 1);
+
+    writeln!(out, "Formation: {}", result.formation(config))?;
+    writeln!(
+        out,
+        "{:<position_width$} {:<name_width$} {:<team_width$} {:>6} {:>7}  Role",
+        "Pos", "Player", "Team", "Value", "Points"
+    )?;
+
+    let starting_xi = result.starting_xi.iter().map(|p| (p, None));
+    let bench = result
+        .bench
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p, Some(i + 1)));
+    for (player, bench_order) in starting_xi.chain(bench) {
+        let role = match (role(result, player), bench_order) {
+            (_, Some(order)) => format!("Bench {}", order),
+            ("captain", _) => "C".to_string(),
+            ("vice_captain", _) => "VC".to_string(),
+            _ => String::new(),
+        };
+        let line = format!(
+            "{:<position_width$} {:<name_width$} {:<team_width$} {:>6} {:>7.2}  {}",
+            player.position, player.name, player.team, player.value, player.predicted_points, role
+        );
+        writeln!(out, "{}", line.trim_end())?;
+    }
+
+    write_totals(out, result, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use serde_json::Value;
+
+    #[test]
+    fn json_reports_follow_the_schema() {
+        let config = SelectionConfig::default();
+        let mut squad = fixtures::squad();
+        for (i, player) in squad.iter_mut().enumerate() {
+            player.predicted_points = i as f32;
+        }
+        let result = SelectionResult::new(squad, &config);
+        let mut out = Vec::new();
+        write_selection(&mut out, &result, &config, OutputFormat::Json).unwrap();
+        let report: Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(report["schema_version"], SCHEMA_VERSION);
+        assert_eq!(report["formation"], result.formation(&config));
+        assert_eq!(report["fitness"], result.score);
+        let elements = |key: &str| -> Vec<u64> {
+            report[key]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|p| p["element"].as_u64().unwrap())
+                .collect()
+        };
+        let starting_xi = elements("starting_xi");
+        assert_eq!(starting_xi.len(), config.rules.starters());
+        assert_eq!(elements("bench").len(), config.rules.bench);
+        for key in ["captain", "vice_captain"] {
+            assert!(starting_xi.contains(&report[key].as_u64().unwrap()));
+        }
+        assert_ne!(report["captain"], report["vice_captain"]);
+    }
+}
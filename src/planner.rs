@@ -0,0 +1,545 @@
+use crate::player::Player;
+use crate::squad::{fitness, satisfies_constraints, SelectionConfig, SelectionResult};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::File;
+
+// Default planning parameters
+const HORIZON: usize = 5;
+const MAX_FREE_TRANSFERS: usize = 5;
+const MAX_PAID_TRANSFERS: usize = 1;
+const TRANSFER_HIT: f32 = 4.0;
+const BEAM_WIDTH: usize = 50;
+// Best single moves kept per state, from which its weekly transfers are drawn
+const CANDIDATE_MOVES: usize = 30;
+// Best sets of each number of moves kept and extended by another move
+const MOVE_SETS: usize = 500;
+
+/// Settings for planning transfers across several gameweeks.
+#[derive(Debug, Clone)]
+pub struct PlanConfig {
+    /// First gameweek to plan, or the earliest one with predictions.
+    pub first_gameweek: Option<u32>,
+    /// Number of gameweeks to plan.
+    pub horizon: usize,
+    /// Money in the bank on top of the current squad's value.
+    pub bank: f32,
+    /// Free transfers available in the first gameweek.
+    pub free_transfers: usize,
+    /// Most free transfers that can be banked.
+    pub max_free_transfers: usize,
+    /// Transfers beyond the free ones allowed in a week, each costing
+    /// `transfer_hit`.
+    pub max_paid_transfers: usize,
+    /// Points deducted for each transfer beyond the free ones.
+    pub transfer_hit: f32,
+    /// Plans kept after each gameweek of the search.
+    pub beam_width: usize,
+}
+
+impl Default for PlanConfig {
+    fn default() -> Self {
+        PlanConfig {
+            first_gameweek: None,
+            horizon: HORIZON,
+            bank: 0.0,
+            free_transfers: 1,
+            max_free_transfers: MAX_FREE_TRANSFERS,
+            max_paid_transfers: MAX_PAID_TRANSFERS,
+            transfer_hit: TRANSFER_HIT,
+            beam_width: BEAM_WIDTH,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SquadRow {
+    element: u32,
+}
+
+/// Read the `element` ids of a squad from a CSV file with an `element` column.
+pub fn read_squad(path: &str) -> Result<Vec<u32>, Box<dyn Error>> {
+    let file = File::open(path)?;
+    let mut rdr = csv::Reader::from_reader(file);
+    let mut squad = Vec::new();
+
+    for result in rdr.deserialize() {
+        let row: SquadRow = result?;
+        squad.push(row.element);
+    }
+
+    Ok(squad)
+}
+
+/// One player sold and their replacement.
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    pub sold: Player,
+    pub bought: Player,
+}
+
+/// The transfers made before one gameweek and the resulting team.
+#[derive(Debug, Clone)]
+pub struct GameweekPlan {
+    pub gameweek: u32,
+    pub transfers: Vec<Transfer>,
+    /// Free transfers available before this week's transfers.
+    pub free_transfers: usize,
+    /// Points deducted for transfers beyond the free ones.
+    pub hit: f32,
+    /// Money left in the bank after the transfers.
+    pub bank: f32,
+    /// The squad after transfers, scored on this gameweek's predictions.
+    pub result: SelectionResult,
+}
+
+/// A sequence of weekly transfers over the planning horizon.
+#[derive(Debug, Clone)]
+pub struct TransferPlan {
+    pub gameweeks: Vec<GameweekPlan>,
+    /// Sum of each week's `fitness` less the transfer hits.
+    pub total_score: f32,
+}
+
+// A partial plan: the squad as indices into the player list after the last
+// planned week, and the (sold, bought) transfers made in each week so far
+#[derive(Clone)]
+struct State {
+    squad: Vec<usize>,
+    free_transfers: usize,
+    score: f32,
+    estimate: f32,
+    weeks: Vec<Vec<(usize, usize)>>,
+}
+
+struct Planner<'a> {
+    players: &'a [Player],
+    config: SelectionConfig,
+    plan: &'a PlanConfig,
+    // Players with each week's predicted points, and points summed from each
+    // week to the end of the horizon
+    weekly: Vec<Vec<Player>>,
+    remaining: Vec<Vec<f32>>,
+    start: Vec<usize>,
+}
+
+/// Plan transfers over `plan.horizon` gameweeks, starting from the squad with
+/// the given `element` ids, making each week up to its free transfers plus
+/// `plan.max_paid_transfers`.
+///
+/// Each week is scored with `fitness` on the players' `gameweek_points` for
+/// that week, less `plan.transfer_hit` for every transfer beyond the free
+/// ones. The budget is the current squad's value plus the bank, with players
+/// sold at their current value. Locked players are never sold and excluded
+/// ones never bought.
+///
+/// This is a beam-search heuristic, not a proof of the best plan. Each week's
+/// transfers are drawn from the squad's best single swaps over the rest of
+/// the horizon that keep the club cap on their own, and only the
+/// `plan.beam_width` most promising plans are kept each week, ranked by points
+/// so far plus the points their squad would score if kept unchanged for the
+/// rest of the horizon. On pools small enough that every swap is shortlisted
+/// and every plan fits the beam, the search is exhaustive.
+pub fn plan_transfers(
+    players: &[Player],
+    squad: &[u32],
+    config: &SelectionConfig,
+    plan: &PlanConfig,
+) -> Result<TransferPlan, Box<dyn Error>> {
+    let index: HashMap<u32, usize> = players
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.element, i))
+        .collect();
+    let squad = squad
+        .iter()
+        .map(|e| {
+            index
+                .get(e)
+                .copied()
+                .ok_or_else(|| format!("Squad player {} is not in the player list", e))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+
+    let squad_value: f32 = squad.iter().map(|&i| players[i].value).sum();
+    let config = SelectionConfig {
+        max_value: squad_value + plan.bank,
+        ..config.clone()
+    };
+    let current: Vec<Player> = squad.iter().map(|&i| players[i].clone()).collect();
+    if !satisfies_constraints(&current, &config) {
+        return Err("Current squad does not satisfy the squad rules".into());
+    }
+    let predicted = players
+        .iter()
+        .flat_map(|p| p.gameweek_points.keys().copied());
+    let first_gameweek = match plan.first_gameweek {
+        Some(gw) => gw,
+        None => predicted
+            .min()
+            .ok_or("No per-gameweek predictions to plan with")?,
+    };
+    if plan.horizon == 0 {
+        return Err("Planning horizon must be at least one gameweek".into());
+    }
+
+    let weekly: Vec<Vec<Player>> = (0..plan.horizon)
+        .map(|week| {
+            players
+                .iter()
+                .map(|p| Player {
+                    predicted_points: p
+                        .gameweek_points
+                        .get(&(first_gameweek + week as u32))
+                        .copied()
+                        .unwrap_or(0.0),
+                    ..p.clone()
+                })
+                .collect()
+        })
+        .collect();
+    let mut remaining = vec![vec![0.0; players.len()]; plan.horizon + 1];
+    for week in (0..plan.horizon).rev() {
+        for i in 0..players.len() {
+            remaining[week][i] = remaining[week + 1][i] + weekly[week][i].predicted_points;
+        }
+    }
+
+    let planner = Planner {
+        players,
+        config,
+        plan,
+        weekly,
+        remaining,
+        start: squad.clone(),
+    };
+
+    let mut beam = vec![State {
+        squad,
+        free_transfers: plan.free_transfers,
+        score: 0.0,
+        estimate: 0.0,
+        weeks: Vec::new(),
+    }];
+    for week in 0..plan.horizon {
+        let mut children: HashMap<(Vec<usize>, usize), State> = HashMap::new();
+        for state in &beam {
+            for child in planner.expand(state, week) {
+                let mut key = child.squad.clone();
+                key.sort();
+                let entry = children.entry((key, child.free_transfers));
+                let existing = entry.or_insert_with(|| child.clone());
+                if child.score > existing.score {
+                    *existing = child;
+                }
+            }
+        }
+        beam = children.into_values().collect();
+        beam.sort_by(|a, b| b.estimate.total_cmp(&a.estimate));
+        beam.truncate(plan.beam_width.max(1));
+    }
+
+    let best = beam
+        .into_iter()
+        .max_by(|a, b| a.score.total_cmp(&b.score))
+        .unwrap();
+    Ok(planner.report(best, first_gameweek))
+}
+
+// Sets of up to `most` of `moves`, as indices, with no player sold or bought
+// twice, including the empty set. Sets are built a move at a time, keeping
+// the MOVE_SETS with the largest total gain of each size.
+fn move_sets(moves: &[(f32, usize, usize)], most: usize) -> Vec<Vec<usize>> {
+    let mut sets = vec![Vec::new()];
+    let mut level: Vec<(f32, Vec<usize>)> = vec![(0.0, Vec::new())];
+    for _ in 0..most {
+        let mut next = Vec::new();
+        for (gain, set) in &level {
+            let from = set.last().map_or(0, |&k| k + 1);
+            for (k, &(move_gain, sold, bought)) in moves.iter().enumerate().skip(from) {
+                if set
+                    .iter()
+                    .all(|&j| moves[j].1 != sold && moves[j].2 != bought)
+                {
+                    let mut set = set.clone();
+                    set.push(k);
+                    next.push((gain + move_gain, set));
+                }
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        next.sort_by(|a, b| b.0.total_cmp(&a.0));
+        next.truncate(MOVE_SETS);
+        sets.extend(next.iter().map(|(_, set)| set.clone()));
+        level = next;
+    }
+    sets
+}
+
+impl Planner<'_> {
+    fn team(&self, squad: &[usize], week: usize) -> Vec<Player> {
+        squad
+            .iter()
+            .map(|&i| self.weekly[week][i].clone())
+            .collect()
+    }
+
+    // Children of a state after making no transfers, or up to its free
+    // transfers plus the paid ones allowed, in `week`
+    fn expand(&self, state: &State, week: usize) -> Vec<State> {
+        let moves = self.candidate_moves(&state.squad, week);
+        let most = state.free_transfers + self.plan.max_paid_transfers;
+
+        move_sets(&moves, most)
+            .into_iter()
+            .map(|set| {
+                set.into_iter()
+                    .map(|k| (moves[k].1, moves[k].2))
+                    .collect::<Vec<_>>()
+            })
+            .filter_map(|transfers| {
+                let mut squad = state.squad.clone();
+                for &(sold, bought) in &transfers {
+                    let slot = squad.iter().position(|&i| i == sold).unwrap();
+                    squad[slot] = bought;
+                }
+                let team = self.team(&squad, week);
+                if !satisfies_constraints(&team, &self.config) {
+                    return None;
+                }
+
+                let paid = transfers.len().saturating_sub(state.free_transfers);
+                let hit = paid as f32 * self.plan.transfer_hit;
+                let score = state.score + fitness(&team, &self.config) - hit;
+                let future: f32 = (week + 1..self.plan.horizon)
+                    .map(|w| fitness(&self.team(&squad, w), &self.config))
+                    .sum();
+                let unused = state.free_transfers.saturating_sub(transfers.len());
+
+                let mut weeks = state.weeks.clone();
+                weeks.push(transfers);
+                Some(State {
+                    squad,
+                    free_transfers: (unused + 1).min(self.plan.max_free_transfers),
+                    score,
+                    estimate: score + future,
+                    weeks,
+                })
+            })
+            .collect()
+    }
+
+    // Same-position swaps of unlocked players for ones not excluded that keep
+    // the club cap, as (gain in points over the rest of the horizon, sold,
+    // bought), best first. Budget is left to the caller so that several
+    // swaps can rebalance it between them.
+    fn candidate_moves(&self, squad: &[usize], week: usize) -> Vec<(f32, usize, usize)> {
+        let remaining = &self.remaining[week];
+        let owned: HashSet<usize> = squad.iter().copied().collect();
+        let mut club_counts: HashMap<&str, usize> = HashMap::new();
+        for &i in squad {
+            *club_counts
+                .entry(self.players[i].team.as_str())
+                .or_insert(0) += 1;
+        }
+
+        let mut moves = Vec::new();
+        for &sold in squad {
+            let out = &self.players[sold];
+            if self.config.locked.contains(&out.element) {
+                continue;
+            }
+            for (bought, player) in self.players.iter().enumerate() {
+                if player.position != out.position
+                    || owned.contains(&bought)
+                    || self.config.excluded.contains(&player.element)
+                {
+                    continue;
+                }
+                let club_count = club_counts.get(player.team.as_str()).copied().unwrap_or(0);
+                if player.team != out.team && club_count >= self.config.max_players_per_team {
+                    continue;
+                }
+                moves.push((remaining[bought] - remaining[sold], sold, bought));
+            }
+        }
+
+        moves.sort_by(|a, b| b.0.total_cmp(&a.0));
+        moves.truncate(CANDIDATE_MOVES);
+        moves
+    }
+
+    fn report(&self, best: State, first_gameweek: u32) -> TransferPlan {
+        let mut squad = self.start.clone();
+        let mut free_transfers = self.plan.free_transfers;
+        let mut gameweeks = Vec::new();
+
+        for (week, transfers) in best.weeks.iter().enumerate() {
+            for &(sold, bought) in transfers {
+                let slot = squad.iter().position(|&i| i == sold).unwrap();
+                squad[slot] = bought;
+            }
+            let paid = transfers.len().saturating_sub(free_transfers);
+            let team = self.team(&squad, week);
+            let value: f32 = squad.iter().map(|&i| self.players[i].value).sum();
+
+            gameweeks.push(GameweekPlan {
+                gameweek: first_gameweek + week as u32,
+                transfers: transfers
+                    .iter()
+                    .map(|&(sold, bought)| Transfer {
+                        sold: self.players[sold].clone(),
+                        bought: self.players[bought].clone(),
+                    })
+                    .collect(),
+                free_transfers,
+                hit: paid as f32 * self.plan.transfer_hit,
+                bank: self.config.max_value - value,
+                result: SelectionResult::new(team, &self.config),
+            });
+
+            let unused = free_transfers.saturating_sub(transfers.len());
+            free_transfers = (unused + 1).min(self.plan.max_free_transfers);
+        }
+
+        TransferPlan {
+            gameweeks,
+            total_score: best.score,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, player};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    const WEEKS: u32 = 3;
+
+    // Three keepers and five forwards with random prices and weekly points
+    fn tiny_pool(rng: &mut ChaCha8Rng) -> Vec<Player> {
+        (0..8)
+            .map(|i| {
+                let position = if i < 3 { "GK" } else { "FWD" };
+                let value = rng.gen_range(40..=60) as f32;
+                let mut player = player(i + 1, position, &format!("club_{}", i), value, 0.0);
+                for gw in 1..=WEEKS {
+                    player
+                        .gameweek_points
+                        .insert(gw, rng.gen_range(0..=10) as f32);
+                }
+                player
+            })
+            .collect()
+    }
+
+    // The best total score from `week` on, holding `squad` with `free`
+    // transfers, trying every valid squad each week
+    fn brute_force(
+        squads: &[Vec<Player>],
+        squad: &[Player],
+        week: u32,
+        free: usize,
+        config: &SelectionConfig,
+        plan: &PlanConfig,
+    ) -> f32 {
+        if week > WEEKS {
+            return 0.0;
+        }
+        let mut best = f32::NEG_INFINITY;
+        for next in squads {
+            let transfers = next
+                .iter()
+                .filter(|p| !squad.iter().any(|q| q.element == p.element))
+                .count();
+            if transfers > free + plan.max_paid_transfers {
+                continue;
+            }
+            let hit = transfers.saturating_sub(free) as f32 * plan.transfer_hit;
+            let team: Vec<Player> = next
+                .iter()
+                .map(|p| Player {
+                    predicted_points: p.gameweek_points[&week],
+                    ..p.clone()
+                })
+                .collect();
+            let free = (free.saturating_sub(transfers) + 1).min(plan.max_free_transfers);
+            let score = fitness(&team, config) - hit
+                + brute_force(squads, next, week + 1, free, config, plan);
+            best = best.max(score);
+        }
+        best
+    }
+
+    #[test]
+    fn finds_the_best_plan_on_a_tiny_pool() {
+        for seed in 0..20 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let players = tiny_pool(&mut rng);
+            let start = [1, 4, 5];
+            let plan = PlanConfig {
+                bank: rng.gen_range(0..=20) as f32,
+                free_transfers: rng.gen_range(0..=2),
+                max_paid_transfers: rng.gen_range(0..=2),
+                beam_width: 1000,
+                ..PlanConfig::default()
+            };
+            let config = SelectionConfig {
+                rules: fixtures::three_man_rules(),
+                locked: if seed % 4 == 0 {
+                    HashSet::from([4])
+                } else {
+                    HashSet::new()
+                },
+                ..SelectionConfig::default()
+            };
+            let result = plan_transfers(&players, &start, &config, &plan).unwrap();
+
+            let budget_config = SelectionConfig {
+                max_value: start
+                    .iter()
+                    .map(|&e| players[e as usize - 1].value)
+                    .sum::<f32>()
+                    + plan.bank,
+                ..config.clone()
+            };
+            let mut squads = Vec::new();
+            for gk in 0..3 {
+                for a in 3..8 {
+                    for b in a + 1..8 {
+                        let squad =
+                            vec![players[gk].clone(), players[a].clone(), players[b].clone()];
+                        if satisfies_constraints(&squad, &budget_config) {
+                            squads.push(squad);
+                        }
+                    }
+                }
+            }
+            let current: Vec<Player> = start
+                .iter()
+                .map(|&e| players[e as usize - 1].clone())
+                .collect();
+            let best = brute_force(
+                &squads,
+                &current,
+                1,
+                plan.free_transfers,
+                &budget_config,
+                &plan,
+            );
+
+            assert!(
+                (result.total_score - best).abs() < 1e-4,
+                "seed {}: planned {} but the best plan scores {}",
+                seed,
+                result.total_score,
+                best
+            );
+        }
+    }
+}
@@ -0,0 +1,939 @@
+use crate::rules::SquadRules;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::str::FromStr;
+
+/// A player available for selection, as read from the CSV input.
+#[derive(Debug, Clone)]
+pub struct Player {
+    pub element: u32,
+    pub name: String,
+    pub value: f32,
+    pub position: String,
+    pub team: String,
+    /// The points every optimiser maximises: the `predicted_points` column,
+    /// the first gameweek's prediction, or a `GameweekWindow` sum.
+    pub predicted_points: f32,
+    /// Predicted points by gameweek number.
+    pub gameweek_points: BTreeMap<u32, f32>,
+}
+
+// The named columns of a row; `gwN_pts` columns are read separately
+#[derive(Deserialize)]
+struct PlayerRow {
+    element: u32,
+    name: String,
+    value: f32,
+    position: String,
+    team: String,
+    predicted_points: Option<f32>,
+    gameweek: Option<u32>,
+    points: Option<f32>,
+}
+
+// Gameweek number of a wide-format column such as `gw12_pts`
+fn gameweek_column(header: &str) -> Option<u32> {
+    header
+        .strip_prefix("gw")?
+        .strip_suffix("_pts")?
+        .parse()
+        .ok()
+}
+
+// Columns every player CSV needs
+const REQUIRED_COLUMNS: [&str; 5] = ["element", "name", "value", "position", "team"];
+
+/// How `read_csv` treats rows that fail validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Validation {
+    /// Fail, listing every problem in the file.
+    #[default]
+    Strict,
+    /// Skip the bad rows and report them with the players read.
+    Lenient,
+}
+
+/// What to do with predicted points that are NaN or infinite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NanPolicy {
+    /// Treat them as a validation problem.
+    #[default]
+    Reject,
+    /// Replace them with zero.
+    Zero,
+    /// Replace them with the average of the player's position at the same
+    /// club, or of the whole position if no teammate has a finite value.
+    Impute,
+}
+
+impl FromStr for NanPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(NanPolicy::Reject),
+            "zero" => Ok(NanPolicy::Zero),
+            "impute" => Ok(NanPolicy::Impute),
+            _ => Err(format!(
+                "Unknown NaN policy '{}', expected reject, zero or impute",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for NanPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            NanPolicy::Reject => "reject",
+            NanPolicy::Zero => "zero",
+            NanPolicy::Impute => "impute",
+        };
+        f.write_str(name)
+    }
+}
+
+/// How `read_csv` checks and cleans its input.
+#[derive(Debug, Clone, Default)]
+pub struct CsvConfig {
+    pub validation: Validation,
+    pub nan_policy: NanPolicy,
+    /// The positions players may have, and their aliases.
+    pub rules: SquadRules,
+}
+
+/// What is wrong with a field of the player CSV.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// The header has no such column.
+    MissingColumn,
+    /// The row has a different number of fields from the header.
+    FieldCount {
+        expected: u64,
+        found: u64,
+    },
+    /// The field is empty.
+    Missing,
+    /// The field is not a valid value of the column's type.
+    Unparsable {
+        text: String,
+        reason: String,
+    },
+    /// A position the squad rules do not know, with the ones they do.
+    UnknownPosition {
+        position: String,
+        known: Vec<String>,
+    },
+    NegativeValue(f32),
+    /// NaN or infinite.
+    NotFinite(f32),
+    /// The `element` id was already given to the player on an earlier row.
+    DuplicateElement {
+        first_row: usize,
+    },
+    /// A long-format row of an element disagrees with the element's first row
+    /// in this column.
+    ConflictingRow {
+        first_row: usize,
+    },
+    /// The element's prediction for this gameweek was already given on an
+    /// earlier row.
+    DuplicateGameweek {
+        gameweek: u32,
+        first_row: usize,
+    },
+    /// The row has no `predicted_points`, `gwN_pts` or `gameweek` and
+    /// `points` prediction.
+    NoPredictions,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::MissingColumn => write!(f, "no such column"),
+            ValidationError::FieldCount { expected, found } => {
+                write!(f, "{} fields where the header has {}", found, expected)
+            }
+            ValidationError::Missing => write!(f, "no value"),
+            ValidationError::Unparsable { text, reason } => {
+                write!(f, "cannot read '{}': {}", text, reason)
+            }
+            ValidationError::UnknownPosition { position, known } => {
+                write!(
+                    f,
+                    "unknown position '{}', expected one of {}",
+                    position,
+                    known.join(", ")
+                )
+            }
+            ValidationError::NegativeValue(value) => write!(f, "negative value {}", value),
+            ValidationError::NotFinite(value) => write!(f, "{} is not a finite number", value),
+            ValidationError::DuplicateElement { first_row } => {
+                write!(f, "element already used on row {}", first_row)
+            }
+            ValidationError::ConflictingRow { first_row } => {
+                write!(f, "differs from the element's row {}", first_row)
+            }
+            ValidationError::DuplicateGameweek {
+                gameweek,
+                first_row,
+            } => write!(
+                f,
+                "gameweek {} already given on row {}",
+                gameweek, first_row
+            ),
+            ValidationError::NoPredictions => write!(f, "no predicted points"),
+        }
+    }
+}
+
+/// A problem with a row of the player CSV.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowProblem {
+    /// Record of the file, counting the header as row 1; the line number
+    /// unless a quoted field spans lines.
+    pub row: usize,
+    /// The column at fault, if the problem is with one field.
+    pub column: Option<String>,
+    pub error: ValidationError,
+}
+
+impl fmt::Display for RowProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.column {
+            Some(column) => write!(f, "row {}, {}: {}", self.row, column, self.error),
+            None => write!(f, "row {}: {}", self.row, self.error),
+        }
+    }
+}
+
+/// Every problem found in a player CSV that could not be read.
+pub struct InvalidRows(pub Vec<RowProblem>);
+
+impl fmt::Display for InvalidRows {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} problem(s) in the player CSV:", self.0.len())?;
+        for problem in &self.0 {
+            write!(f, "\n  {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+// Shown by `main` when it returns the error, so list the problems readably
+impl fmt::Debug for InvalidRows {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Error for InvalidRows {}
+
+/// The players read from a CSV file, with the rows skipped in lenient mode.
+#[derive(Debug, Clone)]
+pub struct PlayerCsv {
+    pub players: Vec<Player>,
+    /// Data rows in the file.
+    pub rows: usize,
+    /// Rows left out because of the problems listed in `problems`.
+    pub skipped_rows: usize,
+    pub problems: Vec<RowProblem>,
+    /// Non-finite predicted points replaced under the `NanPolicy`.
+    pub replaced_points: usize,
+}
+
+impl PlayerCsv {
+    /// A line on how many players were read, followed by any problems.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Read {} players from {} rows",
+            self.players.len(),
+            self.rows
+        );
+        if self.replaced_points > 0 {
+            summary += &format!(", replacing {} non-finite points", self.replaced_points);
+        }
+        if self.skipped_rows > 0 {
+            summary += &format!(", skipping {} with problems:", self.skipped_rows);
+            for problem in &self.problems {
+                summary += &format!("\n  {}", problem);
+            }
+        }
+        summary
+    }
+}
+
+// Problems found in one row, by column
+type FieldProblems = Vec<(String, ValidationError)>;
+
+// The column a deserialize error is about and what went wrong
+fn field_problem(
+    err: &csv::Error,
+    record: &csv::StringRecord,
+    headers: &csv::StringRecord,
+) -> Option<(String, ValidationError)> {
+    let csv::ErrorKind::Deserialize { err, .. } = err.kind() else {
+        return None;
+    };
+    let i = err.field()? as usize;
+    let column = headers.get(i).unwrap_or_default().to_string();
+    let text = record.get(i).unwrap_or_default().trim();
+    let error = if text.is_empty() {
+        ValidationError::Missing
+    } else {
+        ValidationError::Unparsable {
+            text: text.to_string(),
+            reason: err.kind().to_string(),
+        }
+    };
+    Some((column, error))
+}
+
+// Read and check one row, returning every problem found in it
+fn parse_row(
+    record: &csv::StringRecord,
+    headers: &csv::StringRecord,
+    gameweek_columns: &[(usize, u32)],
+    config: &CsvConfig,
+) -> Result<(PlayerRow, BTreeMap<u32, f32>), FieldProblems> {
+    let mut problems = Vec::new();
+    let not_finite = |column: &str, points: f32| {
+        (config.nan_policy == NanPolicy::Reject && !points.is_finite())
+            .then(|| (column.to_string(), ValidationError::NotFinite(points)))
+    };
+
+    let mut gameweek_points = BTreeMap::new();
+    for &(i, gw) in gameweek_columns {
+        let column = &headers[i];
+        match record.get(i).map(str::trim) {
+            Some("") | None => {}
+            Some(field) => match field.parse::<f32>() {
+                Ok(points) => {
+                    problems.extend(not_finite(column, points));
+                    gameweek_points.insert(gw, points);
+                }
+                Err(err) => problems.push((
+                    column.to_string(),
+                    ValidationError::Unparsable {
+                        text: field.to_string(),
+                        reason: err.to_string(),
+                    },
+                )),
+            },
+        }
+    }
+
+    let mut row: PlayerRow = match record.deserialize(Some(headers)) {
+        Ok(row) => row,
+        Err(err) => {
+            let problem = field_problem(&err, record, headers).unwrap_or_else(|| {
+                let reason = err.to_string();
+                let text = String::new();
+                (String::new(), ValidationError::Unparsable { text, reason })
+            });
+            problems.push(problem);
+            return Err(problems);
+        }
+    };
+
+    if let Some(points) = row.predicted_points {
+        problems.extend(not_finite("predicted_points", points));
+    }
+    if let Some(points) = row.points {
+        problems.extend(not_finite("points", points));
+    }
+    if !row.value.is_finite() {
+        problems.push(("value".to_string(), ValidationError::NotFinite(row.value)));
+    } else if row.value < 0.0 {
+        problems.push((
+            "value".to_string(),
+            ValidationError::NegativeValue(row.value),
+        ));
+    }
+    match config.rules.position_name(&row.position) {
+        Some(name) => row.position = name.to_string(),
+        None => {
+            let error = if row.position.trim().is_empty() {
+                ValidationError::Missing
+            } else {
+                ValidationError::UnknownPosition {
+                    position: row.position.clone(),
+                    known: config
+                        .rules
+                        .positions
+                        .iter()
+                        .map(|p| p.name.clone())
+                        .collect(),
+                }
+            };
+            problems.push(("position".to_string(), error));
+        }
+    }
+    for (column, text) in [("name", &row.name), ("team", &row.team)] {
+        if text.trim().is_empty() {
+            problems.push((column.to_string(), ValidationError::Missing));
+        }
+    }
+    if row.predicted_points.is_none() && row.points.is_none() && gameweek_points.is_empty() {
+        problems.push((
+            "predicted_points".to_string(),
+            ValidationError::NoPredictions,
+        ));
+    }
+
+    if problems.is_empty() {
+        Ok((row, gameweek_points))
+    } else {
+        Err(problems)
+    }
+}
+
+/// Read every player from a CSV file with `element`, `name`, `value`,
+/// `position` and `team` columns.
+///
+/// Predictions come from a `predicted_points` column, from wide-format
+/// `gw1_pts, gw2_pts, ...` columns, or from long-format `gameweek` and
+/// `points` columns with one row per player and gameweek.
+///
+/// Every row is validated: fields must parse, values be non-negative,
+/// positions known to `config.rules`, by name or alias, and `element` ids
+/// unique, except that an element's long-format rows may repeat it for other
+/// gameweeks with the same name, value, position and team. Aliased positions
+/// are read as the position's name. Points that are NaN or infinite are a
+/// problem or replaced, as `config.nan_policy` says.
+/// In strict mode any problem fails the read with an `InvalidRows` error
+/// listing them all; in lenient mode the bad rows are skipped and reported.
+pub fn read_csv(path: &str, config: &CsvConfig) -> Result<PlayerCsv, Box<dyn Error>> {
+    let file = File::open(path)?;
+    let mut rdr = csv::Reader::from_reader(file);
+    let headers = rdr.headers()?.clone();
+    let missing: Vec<RowProblem> = REQUIRED_COLUMNS
+        .iter()
+        .filter(|column| !headers.iter().any(|header| header == **column))
+        .map(|column| RowProblem {
+            row: 1,
+            column: Some(column.to_string()),
+            error: ValidationError::MissingColumn,
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(Box::new(InvalidRows(missing)));
+    }
+    let gameweek_columns: Vec<(usize, u32)> = headers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, header)| gameweek_column(header).map(|gw| (i, gw)))
+        .collect();
+
+    let mut players: Vec<Player> = Vec::new();
+    let mut long_rows: HashMap<u32, usize> = HashMap::new();
+    // Row of each element's first player
+    let mut first_rows: HashMap<u32, usize> = HashMap::new();
+    // Row of each element's long-format prediction for a gameweek
+    let mut gameweek_rows: HashMap<(u32, u32), usize> = HashMap::new();
+    let mut problems = Vec::new();
+    let mut rows = 0;
+    let mut skipped_rows = 0;
+
+    for result in rdr.records() {
+        rows += 1;
+        let record = match result {
+            Ok(record) => record,
+            Err(err) => match err.kind() {
+                csv::ErrorKind::UnequalLengths {
+                    expected_len, len, ..
+                } => {
+                    skipped_rows += 1;
+                    problems.push(RowProblem {
+                        row: rows + 1,
+                        column: None,
+                        error: ValidationError::FieldCount {
+                            expected: *expected_len,
+                            found: *len,
+                        },
+                    });
+                    continue;
+                }
+                _ => return Err(err.into()),
+            },
+        };
+        let line = rows + 1;
+        let mut skip = |row_problems: FieldProblems| {
+            skipped_rows += 1;
+            problems.extend(row_problems.into_iter().map(|(column, error)| RowProblem {
+                row: line,
+                column: Some(column).filter(|column| !column.is_empty()),
+                error,
+            }));
+        };
+        let (row, mut gameweek_points) =
+            match parse_row(&record, &headers, &gameweek_columns, config) {
+                Ok(parsed) => parsed,
+                Err(row_problems) => {
+                    skip(row_problems);
+                    continue;
+                }
+            };
+
+        if let (Some(gw), Some(points)) = (row.gameweek, row.points) {
+            gameweek_points.insert(gw, points);
+
+            // Later long-format rows only add another gameweek to the player,
+            // and must agree with the first on who the player is
+            if let Some(&i) = long_rows.get(&row.element) {
+                let player = &mut players[i];
+                let first_row = first_rows[&row.element];
+                let mut row_problems: FieldProblems = [
+                    ("name", row.name != player.name),
+                    ("value", row.value != player.value),
+                    ("position", row.position != player.position),
+                    ("team", row.team != player.team),
+                ]
+                .into_iter()
+                .filter(|&(_, differs)| differs)
+                .map(|(column, _)| {
+                    let error = ValidationError::ConflictingRow { first_row };
+                    (column.to_string(), error)
+                })
+                .collect();
+                if let Some(&first_row) = gameweek_rows.get(&(row.element, gw)) {
+                    row_problems.push((
+                        "gameweek".to_string(),
+                        ValidationError::DuplicateGameweek {
+                            gameweek: gw,
+                            first_row,
+                        },
+                    ));
+                }
+                if !row_problems.is_empty() {
+                    skip(row_problems);
+                    continue;
+                }
+                gameweek_rows.insert((row.element, gw), line);
+                player.gameweek_points.extend(gameweek_points);
+                if row.predicted_points.is_none() {
+                    player.predicted_points = player
+                        .gameweek_points
+                        .values()
+                        .next()
+                        .copied()
+                        .unwrap_or(0.0);
+                }
+                continue;
+            }
+        }
+        if let Some(&first_row) = first_rows.get(&row.element) {
+            skip(vec![(
+                "element".to_string(),
+                ValidationError::DuplicateElement { first_row },
+            )]);
+            continue;
+        }
+        first_rows.insert(row.element, line);
+        if let (Some(gw), Some(_)) = (row.gameweek, row.points) {
+            long_rows.insert(row.element, players.len());
+            gameweek_rows.insert((row.element, gw), line);
+        }
+
+        let predicted_points = row
+            .predicted_points
+            .or_else(|| gameweek_points.values().next().copied())
+            .unwrap_or(0.0);
+        players.push(Player {
+            element: row.element,
+            name: row.name,
+            value: row.value,
+            position: row.position,
+            team: row.team,
+            predicted_points,
+            gameweek_points,
+        });
+    }
+
+    if config.validation == Validation::Strict && !problems.is_empty() {
+        return Err(Box::new(InvalidRows(problems)));
+    }
+    let replaced_points = replace_non_finite(&mut players, config.nan_policy);
+    Ok(PlayerCsv {
+        players,
+        rows,
+        skipped_rows,
+        problems,
+        replaced_points,
+    })
+}
+
+// A player's `predicted_points`, keyed by `None`, and gameweek points
+fn points(player: &Player) -> impl Iterator<Item = (Option<u32>, f32)> + '_ {
+    let overall = std::iter::once((None, player.predicted_points));
+    overall.chain(
+        player
+            .gameweek_points
+            .iter()
+            .map(|(&gw, &points)| (Some(gw), points)),
+    )
+}
+
+// Mean of the finite values of each group
+fn finite_means<K: Eq + Hash>(values: impl Iterator<Item = (K, f32)>) -> HashMap<K, f32> {
+    let mut sums: HashMap<K, (f32, usize)> = HashMap::new();
+    for (key, value) in values.filter(|(_, value)| value.is_finite()) {
+        let sum = sums.entry(key).or_default();
+        sum.0 += value;
+        sum.1 += 1;
+    }
+    sums.into_iter()
+        .map(|(key, (sum, count))| (key, sum / count as f32))
+        .collect()
+}
+
+/// Replace predicted points that are NaN or infinite, both `predicted_points`
+/// and `gameweek_points`, as `policy` says, returning how many were replaced.
+/// With `NanPolicy::Reject` nothing is replaced.
+pub fn replace_non_finite(players: &mut [Player], policy: NanPolicy) -> usize {
+    if policy == NanPolicy::Reject {
+        return 0;
+    }
+    // Averages by position and club, then by position
+    let by_club = finite_means(players.iter().flat_map(|p| {
+        points(p).map(move |(gw, points)| ((p.position.as_str(), p.team.as_str(), gw), points))
+    }));
+    let by_position = finite_means(
+        players
+            .iter()
+            .flat_map(|p| points(p).map(move |(gw, points)| ((p.position.as_str(), gw), points))),
+    );
+    let replacement = |p: &Player, gw: Option<u32>| match policy {
+        NanPolicy::Impute => by_club
+            .get(&(p.position.as_str(), p.team.as_str(), gw))
+            .or_else(|| by_position.get(&(p.position.as_str(), gw)))
+            .copied()
+            .unwrap_or(0.0),
+        _ => 0.0,
+    };
+    let replacements: Vec<(usize, Option<u32>, f32)> = players
+        .iter()
+        .enumerate()
+        .flat_map(|(i, p)| {
+            points(p)
+                .filter(|(_, points)| !points.is_finite())
+                .map(move |(gw, _)| (i, gw, replacement(p, gw)))
+        })
+        .collect();
+
+    for &(i, gw, value) in &replacements {
+        match gw {
+            None => players[i].predicted_points = value,
+            Some(gw) => {
+                players[i].gameweek_points.insert(gw, value);
+            }
+        }
+    }
+    replacements.len()
+}
+
+#[derive(Deserialize)]
+struct PredictionRow {
+    element: u32,
+    gameweek: u32,
+    points: f32,
+}
+
+/// Add long-format predictions from a CSV file with `element,gameweek,points`
+/// rows to the players' `gameweek_points`, handling points that are NaN or
+/// infinite as `nan_policy` says.
+pub fn read_predictions(
+    path: &str,
+    players: &mut [Player],
+    nan_policy: NanPolicy,
+) -> Result<(), Box<dyn Error>> {
+    let file = File::open(path)?;
+    let mut rdr = csv::Reader::from_reader(file);
+    let index: HashMap<u32, usize> = players
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.element, i))
+        .collect();
+
+    let mut problems = Vec::new();
+    for (i, result) in rdr.deserialize().enumerate() {
+        let row: PredictionRow = result?;
+        if nan_policy == NanPolicy::Reject && !row.points.is_finite() {
+            problems.push(RowProblem {
+                // After the header, counting from 1
+                row: i + 2,
+                column: Some("points".to_string()),
+                error: ValidationError::NotFinite(row.points),
+            });
+        }
+        if let Some(&i) = index.get(&row.element) {
+            players[i].gameweek_points.insert(row.gameweek, row.points);
+        }
+    }
+    if !problems.is_empty() {
+        return Err(Box::new(InvalidRows(problems)));
+    }
+
+    replace_non_finite(players, nan_policy);
+    Ok(())
+}
+
+/// Read a list of players from a text file with one `element` id or name per
+/// line. Blank lines and lines starting with `#` are skipped.
+pub fn read_player_list(path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Find the `element` ids of players given by id or by name. Names match
+/// without regard to case and must name exactly one player.
+pub fn find_players(players: &[Player], names: &[String]) -> Result<HashSet<u32>, Box<dyn Error>> {
+    let mut elements = HashSet::new();
+    for name in names {
+        if let Ok(element) = name.parse::<u32>() {
+            if players.iter().any(|p| p.element == element) {
+                elements.insert(element);
+                continue;
+            }
+        }
+
+        let matches: Vec<&Player> = players
+            .iter()
+            .filter(|p| p.name.eq_ignore_ascii_case(name))
+            .collect();
+        match matches.as_slice() {
+            [player] => {
+                elements.insert(player.element);
+            }
+            [] => return Err(format!("No player named '{}'", name).into()),
+            _ => {
+                let ids: Vec<String> = matches.iter().map(|p| p.element.to_string()).collect();
+                return Err(format!(
+                    "'{}' names several players, use one of their ids: {}",
+                    name,
+                    ids.join(", ")
+                )
+                .into());
+            }
+        }
+    }
+    Ok(elements)
+}
+
+/// Weights for combining a run of gameweek predictions into one score.
+#[derive(Debug, Clone)]
+pub struct GameweekWindow {
+    pub first_gameweek: u32,
+    /// Weight of each gameweek from `first_gameweek` on.
+    pub weights: Vec<f32>,
+}
+
+impl GameweekWindow {
+    /// `weeks` gameweeks from `first_gameweek`, each worth `discount` times
+    /// the one before.
+    pub fn discounted(first_gameweek: u32, weeks: usize, discount: f32) -> Self {
+        GameweekWindow {
+            first_gameweek,
+            weights: (0..weeks).map(|k| discount.powi(k as i32)).collect(),
+        }
+    }
+
+    /// Weighted sum of a player's predictions over the window, counting
+    /// missing gameweeks as zero.
+    pub fn points(&self, player: &Player) -> f32 {
+        self.weights
+            .iter()
+            .enumerate()
+            .map(|(k, weight)| {
+                let gw = self.first_gameweek + k as u32;
+                weight * player.gameweek_points.get(&gw).copied().unwrap_or(0.0)
+            })
+            .sum()
+    }
+}
+
+/// Set every player's `predicted_points` to their sum over the window, so
+/// that the optimisers score squads across those gameweeks.
+pub fn apply_window(players: &mut [Player], window: &GameweekWindow) {
+    for player in players {
+        player.predicted_points = window.points(player);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Read `contents` as a player CSV from a file named after the test
+    fn read(name: &str, contents: &str, config: CsvConfig) -> Result<PlayerCsv, String> {
+        let path =
+            std::env::temp_dir().join(format!("team_selector_{}_{}.csv", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        let result = read_csv(path.to_str().unwrap(), &config);
+        fs::remove_file(&path).unwrap();
+        result.map_err(|err| err.to_string())
+    }
+
+    fn config(validation: Validation, nan_policy: NanPolicy) -> CsvConfig {
+        CsvConfig {
+            validation,
+            nan_policy,
+            ..CsvConfig::default()
+        }
+    }
+
+    const BAD_ROWS: &str = "element,name,value,position,team,predicted_points
+1,gk_1,45,GK,ARS,3.2
+2,gk_2,-5,GKP,ARS,NaN
+3,def_3,abc,DEF,CHE,2.0
+1,def_1,50,DEF,CHE,2.0
+4,mid_4,50,MID,LIV
+5,mid_5,55,MID,LIV,4.1
+";
+
+    #[test]
+    fn strict_mode_reports_every_problem() {
+        let err = read(
+            "strict",
+            BAD_ROWS,
+            config(Validation::Strict, NanPolicy::Reject),
+        )
+        .unwrap_err();
+        let lines: Vec<&str> = err.lines().skip(1).map(str::trim).collect();
+        assert_eq!(
+            lines,
+            [
+                "row 3, predicted_points: NaN is not a finite number",
+                "row 3, value: negative value -5",
+                "row 3, position: unknown position 'GKP', expected one of GK, DEF, MID, FWD",
+                "row 4, value: cannot read 'abc': invalid float literal",
+                "row 5, element: element already used on row 2",
+                "row 6: 5 fields where the header has 6",
+            ]
+        );
+    }
+
+    #[test]
+    fn lenient_mode_skips_bad_rows() {
+        let csv = read(
+            "lenient",
+            BAD_ROWS,
+            config(Validation::Lenient, NanPolicy::Reject),
+        )
+        .unwrap();
+        let elements: Vec<u32> = csv.players.iter().map(|p| p.element).collect();
+        assert_eq!(elements, [1, 5]);
+        assert_eq!((csv.rows, csv.skipped_rows, csv.problems.len()), (6, 4, 6));
+        assert!(csv
+            .summary()
+            .starts_with("Read 2 players from 6 rows, skipping 4 with problems:"));
+    }
+
+    #[test]
+    fn long_format_rows_are_not_duplicates() {
+        let contents = "element,name,value,position,team,gameweek,points
+1,gk_1,45,GK,ARS,1,3.0
+1,gk_1,45,GK,ARS,2,4.5
+";
+        let csv = read(
+            "long",
+            contents,
+            config(Validation::Strict, NanPolicy::Reject),
+        )
+        .unwrap();
+        assert_eq!(csv.players.len(), 1);
+        assert_eq!(csv.players[0].gameweek_points.len(), 2);
+
+        // Rows that name another player or repeat a gameweek are rejected
+        let conflicting = "element,name,value,position,team,gameweek,points
+1,Alice,45,GK,ARS,1,3.0
+1,Bob,90,FWD,ARS,2,4.5
+1,Alice,45,GK,ARS,1,9.0
+";
+        let csv = read(
+            "long_conflicts",
+            conflicting,
+            config(Validation::Lenient, NanPolicy::Reject),
+        )
+        .unwrap();
+        assert_eq!(csv.skipped_rows, 2);
+        assert_eq!(csv.players[0].gameweek_points[&1], 3.0);
+        let columns: Vec<(usize, &str)> = csv
+            .problems
+            .iter()
+            .map(|p| (p.row, p.column.as_deref().unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            columns,
+            [(3, "name"), (3, "value"), (3, "position"), (4, "gameweek")]
+        );
+        assert_eq!(
+            csv.problems[3].error,
+            ValidationError::DuplicateGameweek {
+                gameweek: 1,
+                first_row: 2
+            }
+        );
+    }
+
+    #[test]
+    fn reads_aliased_positions_as_their_name() {
+        let mut config = config(Validation::Lenient, NanPolicy::Reject);
+        config.rules.positions[0].aliases.push("GKP".to_string());
+        let csv = read("aliases", BAD_ROWS, config).unwrap();
+        let positions: Vec<&str> = csv.players.iter().map(|p| p.position.as_str()).collect();
+        assert_eq!(positions, ["GK", "MID"]);
+        assert!(!csv.summary().contains("position"));
+    }
+
+    const NAN_ROWS: &str = "element,name,value,position,team,predicted_points
+1,mid_1,50,MID,LIV,4.0
+2,mid_2,50,MID,LIV,6.0
+3,mid_3,50,MID,LIV,NaN
+4,mid_4,50,MID,CHE,2.0
+5,mid_5,50,MID,ARS,inf
+6,gk_6,50,GK,ARS,NaN
+";
+
+    #[test]
+    fn replaces_non_finite_points() {
+        let points = |nan_policy| {
+            let csv = read("nan", NAN_ROWS, config(Validation::Strict, nan_policy)).unwrap();
+            assert_eq!(csv.replaced_points, 3);
+            csv.players
+                .iter()
+                .map(|p| p.predicted_points)
+                .collect::<Vec<f32>>()
+        };
+        assert_eq!(points(NanPolicy::Zero), [4.0, 6.0, 0.0, 2.0, 0.0, 0.0]);
+        // From the same club, else the same position, else zero
+        assert_eq!(points(NanPolicy::Impute), [4.0, 6.0, 5.0, 2.0, 4.0, 0.0]);
+
+        let err = read(
+            "reject",
+            NAN_ROWS,
+            config(Validation::Strict, NanPolicy::Reject),
+        );
+        assert_eq!(err.unwrap_err().lines().count(), 4);
+    }
+
+    #[test]
+    fn requires_the_player_columns() {
+        let err = read(
+            "columns",
+            "element,name,position\n",
+            config(Validation::Lenient, NanPolicy::Reject),
+        )
+        .unwrap_err();
+        assert!(err.contains("row 1, value: no such column"));
+        assert!(err.contains("row 1, team: no such column"));
+    }
+}
@@ -0,0 +1,323 @@
+use crate::planner::Transfer;
+use crate::player::Player;
+use crate::squad::{fitness, players_by_position, SelectionConfig, SelectionResult};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// Default tabu search parameters
+const TABU_MOVES: usize = 20;
+const TABU_TENURE: usize = 7;
+// Gains below this are treated as rounding noise
+const EPSILON: f32 = 1e-4;
+
+/// Settings for polishing a squad with local search.
+#[derive(Debug, Clone)]
+pub struct PolishConfig {
+    /// Swaps made in a tabu walk away from a local optimum before giving up
+    /// on finding a better squad.
+    pub tabu_moves: usize,
+    /// Swaps for which players sold or bought in a tabu walk cannot be
+    /// bought or sold again.
+    pub tabu_tenure: usize,
+}
+
+impl Default for PolishConfig {
+    fn default() -> Self {
+        PolishConfig {
+            tabu_moves: TABU_MOVES,
+            tabu_tenure: TABU_TENURE,
+        }
+    }
+}
+
+/// A change that raised the squad's fitness.
+#[derive(Debug, Clone)]
+pub struct Improvement {
+    pub transfers: Vec<Transfer>,
+    /// The squad's fitness before and after the change.
+    pub before: f32,
+    pub after: f32,
+}
+
+/// A polished squad and the improvements made to reach it.
+pub struct PolishSolution {
+    pub result: SelectionResult,
+    pub improvements: Vec<Improvement>,
+}
+
+struct LocalSearch<'a> {
+    config: &'a SelectionConfig,
+    // Selectable players of each position, best first
+    pools: HashMap<&'a str, Vec<&'a Player>>,
+}
+
+/// Improve a valid squad by local search until no swap of one or two players
+/// for others of the same position raises its `fitness`.
+///
+/// Steepest ascent takes the best such swap while one improves the squad.
+/// From each local optimum a tabu walk of single swaps, which may lower
+/// fitness, looks for a better squad for up to `polish.tabu_moves` swaps; if
+/// it finds one the ascent resumes from there. The squad returned is always a
+/// local optimum for one- and two-player swaps.
+pub fn polish_team(
+    team: &[Player],
+    players: &[Player],
+    config: &SelectionConfig,
+    polish: &PolishConfig,
+) -> PolishSolution {
+    let mut pools = players_by_position(players, config);
+    for pool in pools.values_mut() {
+        pool.sort_by(|a, b| b.predicted_points.total_cmp(&a.predicted_points));
+    }
+    let search = LocalSearch { config, pools };
+
+    let mut best = team.to_vec();
+    let mut best_score = fitness(&best, config);
+    let mut improvements = Vec::new();
+    loop {
+        while let Some((next, score)) = search.best_move(
+            &best,
+            best_score,
+            &HashSet::new(),
+            true,
+            best_score + EPSILON,
+        ) {
+            improvements.push(improvement(&best, &next, best_score, score));
+            best = next;
+            best_score = score;
+        }
+
+        let Some((next, score)) = search.tabu_walk(&best, best_score, polish) else {
+            break;
+        };
+        improvements.push(improvement(&best, &next, best_score, score));
+        best = next;
+        best_score = score;
+    }
+
+    PolishSolution {
+        result: SelectionResult::new(best, config),
+        improvements,
+    }
+}
+
+// The transfers that turn `before` into `after`, pairing players by position
+fn improvement(before: &[Player], after: &[Player], from: f32, to: f32) -> Improvement {
+    let mut bought: Vec<&Player> = after
+        .iter()
+        .filter(|p| !before.iter().any(|q| q.element == p.element))
+        .collect();
+    let mut transfers = Vec::new();
+    for sold in before
+        .iter()
+        .filter(|p| !after.iter().any(|q| q.element == p.element))
+    {
+        if let Some(i) = bought.iter().position(|p| p.position == sold.position) {
+            transfers.push(Transfer {
+                sold: sold.clone(),
+                bought: bought.remove(i).clone(),
+            });
+        }
+    }
+    Improvement {
+        transfers,
+        before: from,
+        after: to,
+    }
+}
+
+impl LocalSearch<'_> {
+    // Whether replacing `sold` with `bought` keeps the squad within the club
+    // cap and budget
+    fn fits(&self, team: &[Player], sold: &[&Player], bought: &[&Player]) -> bool {
+        let value: f32 = team.iter().map(|p| p.value).sum::<f32>()
+            - sold.iter().map(|p| p.value).sum::<f32>()
+            + bought.iter().map(|p| p.value).sum::<f32>();
+        if value > self.config.max_value {
+            return false;
+        }
+        bought.iter().all(|p| {
+            let count = team.iter().filter(|q| q.team == p.team).count()
+                - sold.iter().filter(|q| q.team == p.team).count()
+                + bought.iter().filter(|q| q.team == p.team).count();
+            count <= self.config.max_players_per_team
+        })
+    }
+
+    // The best squad one swap away, or two if `pairs` is set, that scores
+    // above `floor` without buying or selling a tabu player. Each swap raises
+    // fitness by at most the captain multiplier times the points gained, which
+    // bounds the search.
+    fn best_move(
+        &self,
+        team: &[Player],
+        score: f32,
+        tabu: &HashSet<u32>,
+        pairs: bool,
+        floor: f32,
+    ) -> Option<(Vec<Player>, f32)> {
+        let multiplier = self.config.captain_multiplier.max(1.0);
+        let gain = |out: &Player, p: &Player| {
+            multiplier * (p.predicted_points - out.predicted_points).max(0.0)
+        };
+        let owned: HashSet<u32> = team.iter().map(|p| p.element).collect();
+        let slots: Vec<(usize, &Vec<&Player>)> = (0..team.len())
+            .filter(|&i| {
+                !self.config.locked.contains(&team[i].element) && !tabu.contains(&team[i].element)
+            })
+            .filter_map(|i| {
+                self.pools
+                    .get(team[i].position.as_str())
+                    .map(|pool| (i, pool))
+            })
+            .collect();
+        let buyable = |p: &Player| !owned.contains(&p.element) && !tabu.contains(&p.element);
+
+        let mut trial = team.to_vec();
+        let mut best: Option<(Vec<Player>, f32)> = None;
+        let mut best_score = floor;
+
+        for &(i, pool) in &slots {
+            for bought in pool.iter().filter(|p| buyable(p)) {
+                if score + gain(&team[i], bought) <= best_score {
+                    break;
+                }
+                if !self.fits(team, &[&team[i]], &[bought]) {
+                    continue;
+                }
+                trial[i] = (*bought).clone();
+                let trial_score = fitness(&trial, self.config);
+                if trial_score > best_score {
+                    best_score = trial_score;
+                    best = Some((trial.clone(), trial_score));
+                }
+                trial[i] = team[i].clone();
+            }
+        }
+
+        if !pairs {
+            return best;
+        }
+        for (a, &(i, first_pool)) in slots.iter().enumerate() {
+            for &(j, second_pool) in &slots[a + 1..] {
+                let top_second = second_pool
+                    .iter()
+                    .find(|p| buyable(p))
+                    .map_or(0.0, |p| gain(&team[j], p));
+                for first in first_pool.iter().filter(|p| buyable(p)) {
+                    let first_gain = gain(&team[i], first);
+                    if score + first_gain + top_second <= best_score {
+                        break;
+                    }
+                    for second in second_pool.iter().filter(|p| buyable(p)) {
+                        if score + first_gain + gain(&team[j], second) <= best_score {
+                            break;
+                        }
+                        if first.element == second.element
+                            || !self.fits(team, &[&team[i], &team[j]], &[first, second])
+                        {
+                            continue;
+                        }
+                        trial[i] = (*first).clone();
+                        trial[j] = (*second).clone();
+                        let trial_score = fitness(&trial, self.config);
+                        if trial_score > best_score {
+                            best_score = trial_score;
+                            best = Some((trial.clone(), trial_score));
+                        }
+                        trial[i] = team[i].clone();
+                        trial[j] = team[j].clone();
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    // Walk from a local optimum by the best non-tabu single swap, even when
+    // it lowers fitness, until a squad beats `score` or the walk runs out
+    fn tabu_walk(
+        &self,
+        team: &[Player],
+        score: f32,
+        polish: &PolishConfig,
+    ) -> Option<(Vec<Player>, f32)> {
+        let mut current = team.to_vec();
+        let mut current_score = score;
+        let mut recent: VecDeque<u32> = VecDeque::new();
+
+        for _ in 0..polish.tabu_moves {
+            let tabu: HashSet<u32> = recent.iter().copied().collect();
+            let (next, next_score) =
+                self.best_move(&current, current_score, &tabu, false, f32::NEG_INFINITY)?;
+            for (sold, bought) in current.iter().zip(&next) {
+                if sold.element != bought.element {
+                    recent.push_back(sold.element);
+                    recent.push_back(bought.element);
+                }
+            }
+            while recent.len() > 2 * polish.tabu_tenure {
+                recent.pop_front();
+            }
+
+            current = next;
+            current_score = next_score;
+            if current_score > score + EPSILON {
+                return Some((current, current_score));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, check_squad};
+    use crate::ga::generate_random_team;
+    use crate::squad::satisfies_constraints;
+    use proptest::prelude::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn polished_squads_are_local_optima(seed: u64) {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let players = fixtures::players(60, seed);
+            let mut config = SelectionConfig::default();
+            fixtures::lock_and_exclude(&players, &mut config, &mut rng);
+            let team = generate_random_team(&players, &config, &mut rng).unwrap();
+            let result = polish_team(&team, &players, &config, &PolishConfig::default()).result;
+            prop_assert_eq!(check_squad(&result.squad, &config), Ok(()));
+
+            // Try every swap of one or two players for others of their position
+            let squad = &result.squad;
+            let candidates = |i: usize| {
+                players.iter().filter(move |p| {
+                    p.position == squad[i].position && !squad.iter().any(|q| q.element == p.element)
+                })
+            };
+            let mut trial = squad.clone();
+            for i in 0..squad.len() {
+                for first in candidates(i) {
+                    trial[i] = first.clone();
+                    if satisfies_constraints(&trial, &config) {
+                        prop_assert!(fitness(&trial, &config) <= result.score + EPSILON);
+                    }
+                    for j in i + 1..squad.len() {
+                        for second in candidates(j).filter(|p| p.element != first.element) {
+                            trial[j] = second.clone();
+                            if satisfies_constraints(&trial, &config) {
+                                prop_assert!(fitness(&trial, &config) <= result.score + EPSILON);
+                            }
+                        }
+                        trial[j] = squad[j].clone();
+                    }
+                }
+                trial[i] = squad[i].clone();
+            }
+        }
+    }
+}
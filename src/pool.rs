@@ -0,0 +1,348 @@
+use crate::player::Player;
+use crate::squad::{SelectionConfig, BENCH_WEIGHT};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+
+// Fresh starts `random_squad` makes before giving up
+const BUILD_ATTEMPTS: usize = 100;
+
+/// A squad as indices into a `Pool`.
+pub(crate) type Squad = Vec<u32>;
+
+// The parts of a player the optimisers use, with position and club interned
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Entry {
+    pub element: u32,
+    pub position: u8,
+    pub club: u16,
+    pub value: f32,
+    pub points: f32,
+    pub locked: bool,
+    pub excluded: bool,
+}
+
+// The player table shared by every squad of a run, so that squads are small
+// vectors of indices rather than copies of `Player`
+pub(crate) struct Pool<'a> {
+    pub players: &'a [Player],
+    pub config: &'a SelectionConfig,
+    pub entries: Vec<Entry>,
+    // Squad places and (min, max) starters by position id, the index of the
+    // position in the squad rules; players whose position has no rules get
+    // the last id, with no places
+    pub slots: Vec<usize>,
+    pub starters: Vec<(usize, usize)>,
+    pub clubs: usize,
+    // Selectable players of each position, cheapest first
+    pub by_position: Vec<Vec<u32>>,
+    pub locked: Vec<u32>,
+}
+
+impl<'a> Pool<'a> {
+    pub fn new(players: &'a [Player], config: &'a SelectionConfig) -> Self {
+        let rules = &config.rules.positions;
+        let mut slots: Vec<usize> = rules.iter().map(|r| r.squad).collect();
+        let mut starters: Vec<(usize, usize)> =
+            rules.iter().map(|r| (r.xi_min, r.xi_max)).collect();
+        slots.push(0);
+        starters.push((0, 0));
+
+        let mut clubs: HashMap<&str, u16> = HashMap::new();
+        let mut entries: Vec<Entry> = Vec::with_capacity(players.len());
+        let mut by_position = vec![Vec::new(); slots.len()];
+        let mut locked = Vec::new();
+        for (i, player) in players.iter().enumerate() {
+            let position = config
+                .rules
+                .position_index(&player.position)
+                .unwrap_or(rules.len());
+            let next_club = clubs.len() as u16;
+            let club = *clubs.entry(player.team.as_str()).or_insert(next_club);
+            let entry = Entry {
+                element: player.element,
+                position: position as u8,
+                club,
+                value: player.value,
+                points: player.predicted_points,
+                locked: config.locked.contains(&player.element),
+                excluded: config.excluded.contains(&player.element),
+            };
+            if entry.locked
+                && !locked
+                    .iter()
+                    .any(|&j| entries[j as usize].element == entry.element)
+            {
+                locked.push(i as u32);
+            }
+            if !entry.excluded && slots[position] > 0 {
+                by_position[position].push(i as u32);
+            }
+            entries.push(entry);
+        }
+        for candidates in &mut by_position {
+            candidates.sort_by(|&a, &b| {
+                entries[a as usize]
+                    .value
+                    .total_cmp(&entries[b as usize].value)
+            });
+        }
+
+        Pool {
+            players,
+            config,
+            entries,
+            slots,
+            starters,
+            clubs: clubs.len(),
+            by_position,
+            locked,
+        }
+    }
+
+    pub fn entry(&self, index: u32) -> &Entry {
+        &self.entries[index as usize]
+    }
+
+    pub fn value(&self, squad: &[u32]) -> f32 {
+        squad.iter().map(|&i| self.entry(i).value).sum()
+    }
+
+    pub fn club_counts(&self, squad: &[u32]) -> Vec<usize> {
+        let mut counts = vec![0; self.clubs];
+        for &i in squad {
+            counts[self.entry(i).club as usize] += 1;
+        }
+        counts
+    }
+
+    pub fn to_players(&self, squad: &[u32]) -> Vec<Player> {
+        squad
+            .iter()
+            .map(|&i| self.players[i as usize].clone())
+            .collect()
+    }
+
+    // The same score as `fitness` on the squad's players
+    pub fn fitness(&self, squad: &[u32]) -> f32 {
+        if self.value(squad) > self.config.max_value {
+            return 0.0;
+        }
+
+        let mut order: Vec<usize> = (0..squad.len()).collect();
+        order.sort_by(|&a, &b| {
+            self.entry(squad[b])
+                .points
+                .total_cmp(&self.entry(squad[a]).points)
+        });
+        let mut counts = vec![0; self.slots.len()];
+        let mut starter = vec![false; squad.len()];
+        let mut picked = 0;
+        for &k in &order {
+            let position = self.entry(squad[k]).position as usize;
+            if counts[position] < self.starters[position].0 {
+                counts[position] += 1;
+                starter[k] = true;
+                picked += 1;
+            }
+        }
+        for &k in &order {
+            if picked == self.config.rules.starters() {
+                break;
+            }
+            let position = self.entry(squad[k]).position as usize;
+            if !starter[k] && counts[position] < self.starters[position].1 {
+                counts[position] += 1;
+                starter[k] = true;
+                picked += 1;
+            }
+        }
+
+        let captain = (0..squad.len())
+            .filter(|&k| starter[k])
+            .map(|k| self.entry(squad[k]).points)
+            .reduce(f32::max)
+            .unwrap_or(0.0);
+        let points: f32 = squad
+            .iter()
+            .zip(&starter)
+            .map(|(&i, &starts)| {
+                let points = self.entry(i).points;
+                if starts {
+                    points
+                } else {
+                    points * BENCH_WEIGHT
+                }
+            })
+            .sum();
+        points + captain * (self.config.captain_multiplier - 1.0)
+    }
+
+    // The same check as `satisfies_constraints` on the squad's players
+    pub fn is_valid(&self, squad: &[u32]) -> bool {
+        if squad.len() != self.config.rules.squad_size()
+            || self.value(squad) > self.config.max_value
+        {
+            return false;
+        }
+        let mut counts = vec![0; self.slots.len()];
+        for (k, &i) in squad.iter().enumerate() {
+            let entry = self.entry(i);
+            counts[entry.position as usize] += 1;
+            if entry.excluded
+                || counts[entry.position as usize] > self.slots[entry.position as usize]
+                || squad[..k]
+                    .iter()
+                    .any(|&j| self.entry(j).element == entry.element)
+            {
+                return false;
+            }
+        }
+        self.club_counts(squad)
+            .iter()
+            .all(|&count| count <= self.config.max_players_per_team)
+            && self.locked.iter().all(|&l| {
+                squad
+                    .iter()
+                    .any(|&i| self.entry(i).element == self.entry(l).element)
+            })
+    }
+
+    // A random valid squad, or why none was found. The locked players are
+    // placed first, then the open places in a random order, each with a
+    // player drawn from those that leave the budget for the cheapest players
+    // of the places still open. That reserve ignores the club cap, so a build
+    // can still run out of players; it then starts again, up to
+    // BUILD_ATTEMPTS times.
+    pub fn random_squad<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<Squad, String> {
+        for _ in 0..BUILD_ATTEMPTS {
+            if let Some(squad) = self.build_squad(rng) {
+                return Ok(squad);
+            }
+        }
+        Err(format!(
+            "No valid squad found in {} attempts: the club cap of {} and budget of {} leave too few players",
+            BUILD_ATTEMPTS, self.config.max_players_per_team, self.config.max_value
+        ))
+    }
+
+    fn build_squad<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Squad> {
+        let mut squad: Squad = self.locked.clone();
+        let mut counts = vec![0; self.slots.len()];
+        let mut club_counts = vec![0; self.clubs];
+        for &i in &squad {
+            counts[self.entry(i).position as usize] += 1;
+            club_counts[self.entry(i).club as usize] += 1;
+        }
+        let mut open: Vec<usize> = Vec::new();
+        for (position, &slots) in self.slots.iter().enumerate() {
+            open.extend(std::iter::repeat_n(
+                position,
+                slots.checked_sub(counts[position])?,
+            ));
+        }
+        open.shuffle(rng);
+        let mut budget = self.config.max_value - self.value(&squad);
+
+        while let Some(position) = open.pop() {
+            let fits = |i: u32| {
+                let entry = self.entry(i);
+                club_counts[entry.club as usize] < self.config.max_players_per_team
+                    && !squad
+                        .iter()
+                        .any(|&j| self.entry(j).element == entry.element)
+            };
+
+            // The cheapest players that could fill the other open places
+            let mut reserve = 0.0;
+            for (other, candidates) in self.by_position.iter().enumerate() {
+                let needed = open.iter().filter(|&&p| p == other).count();
+                let cheapest: Vec<f32> = candidates
+                    .iter()
+                    .filter(|&&i| fits(i))
+                    .take(needed)
+                    .map(|&i| self.entry(i).value)
+                    .collect();
+                if cheapest.len() < needed {
+                    return None;
+                }
+                reserve += cheapest.iter().sum::<f32>();
+            }
+
+            let candidates: Vec<u32> = self.by_position[position]
+                .iter()
+                .copied()
+                .filter(|&i| fits(i) && self.entry(i).value + reserve <= budget)
+                .collect();
+            let &i = candidates.choose(rng)?;
+            let entry = self.entry(i);
+            squad.push(i);
+            club_counts[entry.club as usize] += 1;
+            budget -= entry.value;
+        }
+        Some(squad)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::squad::{fitness, satisfies_constraints};
+    use proptest::prelude::*;
+    use rand::seq::index;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    proptest! {
+        #[test]
+        fn matches_the_player_based_rules(seed: u64, changes in 0..3usize, draft: bool) {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let players = fixtures::players(150, seed);
+            let mut config = SelectionConfig::default();
+            if draft {
+                config.rules = fixtures::draft_rules();
+            }
+            fixtures::lock_and_exclude(&players, &mut config, &mut rng);
+            let pool = Pool::new(&players, &config);
+
+            let mut squad = pool.random_squad(&mut rng).unwrap();
+            let changed = index::sample(&mut rng, squad.len(), changes);
+            for k in changed {
+                squad[k] = rng.gen_range(0..players.len() as u32);
+            }
+            let team = pool.to_players(&squad);
+
+            prop_assert_eq!(pool.is_valid(&squad), satisfies_constraints(&team, &config));
+            prop_assert_eq!(pool.fitness(&squad), fitness(&team, &config));
+        }
+
+        #[test]
+        fn builds_squads_on_the_tightest_budget(seed: u64, slack in 0.0..20.0f32) {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let players = fixtures::players(150, seed);
+            // Without a binding club cap the cheapest players of each
+            // position make a squad
+            let mut config = SelectionConfig {
+                max_players_per_team: 15,
+                ..SelectionConfig::default()
+            };
+            let cheapest: f32 = Pool::new(&players, &config)
+                .by_position
+                .iter()
+                .zip(config.rules.positions.iter())
+                .map(|(candidates, rule)| {
+                    candidates[..rule.squad].iter().map(|&i| players[i as usize].value).sum::<f32>()
+                })
+                .sum();
+
+            config.max_value = cheapest + slack;
+            let pool = Pool::new(&players, &config);
+            let squad = pool.random_squad(&mut rng).unwrap();
+            prop_assert!(pool.is_valid(&squad));
+
+            config.max_value = cheapest - 1.0;
+            prop_assert!(Pool::new(&players, &config).random_squad(&mut rng).is_err());
+        }
+    }
+}
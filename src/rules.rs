@@ -0,0 +1,294 @@
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// A position of the squad rules and how many of it a squad holds.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PositionRule {
+    /// Name of the position, as reported in the output.
+    pub name: String,
+    /// Other spellings of the position in player files, such as "GKP".
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Players of the position in every squad.
+    pub squad: usize,
+    /// Fewest players of the position in a legal starting XI.
+    pub xi_min: usize,
+    /// Most players of the position in a legal starting XI.
+    pub xi_max: usize,
+}
+
+/// The positions, squad counts and formations of a fantasy game. The budget
+/// and club cap are in `SelectionConfig`, as they often change between runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SquadRules {
+    /// Every position, in the order the output lists them.
+    pub positions: Vec<PositionRule>,
+    /// Squad players left out of the starting XI.
+    pub bench: usize,
+}
+
+impl PositionRule {
+    /// A position without aliases.
+    pub fn new(name: &str, squad: usize, xi_min: usize, xi_max: usize) -> Self {
+        PositionRule {
+            name: name.to_string(),
+            aliases: Vec::new(),
+            squad,
+            xi_min,
+            xi_max,
+        }
+    }
+}
+
+impl Default for SquadRules {
+    /// The Fantasy Premier League rules: 2 goalkeepers, 5 defenders, 5
+    /// midfielders and 3 forwards, with 4 on the bench.
+    fn default() -> Self {
+        SquadRules {
+            positions: vec![
+                PositionRule::new("GK", 2, 1, 1),
+                PositionRule::new("DEF", 5, 3, 5),
+                PositionRule::new("MID", 5, 2, 5),
+                PositionRule::new("FWD", 3, 1, 3),
+            ],
+            bench: 4,
+        }
+    }
+}
+
+impl SquadRules {
+    /// Players in every squad.
+    pub fn squad_size(&self) -> usize {
+        self.positions.iter().map(|p| p.squad).sum()
+    }
+
+    /// Players in the starting XI, or however many start under these rules.
+    pub fn starters(&self) -> usize {
+        self.squad_size().saturating_sub(self.bench)
+    }
+
+    /// Index in `positions` of the position named `name`.
+    pub fn position_index(&self, name: &str) -> Option<usize> {
+        self.positions.iter().position(|p| p.name == name)
+    }
+
+    /// The rule for the position named `name`.
+    pub fn position(&self, name: &str) -> Option<&PositionRule> {
+        self.positions.iter().find(|p| p.name == name)
+    }
+
+    /// The name of the position named or aliased `text` in a player file.
+    pub fn position_name(&self, text: &str) -> Option<&str> {
+        self.positions
+            .iter()
+            .find(|p| p.name == text || p.aliases.iter().any(|a| a == text))
+            .map(|p| p.name.as_str())
+    }
+
+    /// Check that the rules allow a squad with a starting XI, a captain and a
+    /// vice-captain.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.positions.is_empty() {
+            return Err("The squad rules have no positions".to_string());
+        }
+        let mut names = HashSet::new();
+        for rule in &self.positions {
+            for name in std::iter::once(&rule.name).chain(&rule.aliases) {
+                if !names.insert(name.as_str()) {
+                    return Err(format!("Position name '{}' is used twice", name));
+                }
+            }
+            if rule.xi_min > rule.xi_max || rule.xi_max > rule.squad {
+                return Err(format!(
+                    "Position {} needs xi_min <= xi_max <= squad, not {} <= {} <= {}",
+                    rule.name, rule.xi_min, rule.xi_max, rule.squad
+                ));
+            }
+        }
+
+        let squad_size = self.squad_size();
+        if self.bench > squad_size {
+            return Err(format!(
+                "A bench of {} is larger than the squad of {}",
+                self.bench, squad_size
+            ));
+        }
+        let starters = self.starters();
+        let fewest: usize = self.positions.iter().map(|p| p.xi_min).sum();
+        let most: usize = self.positions.iter().map(|p| p.xi_max).sum();
+        if starters < fewest || starters > most {
+            return Err(format!(
+                "{} starters do not fit the per-position limits of {} to {}",
+                starters, fewest, most
+            ));
+        }
+        if starters < 2 {
+            return Err("A captain and vice-captain need at least 2 starters".to_string());
+        }
+        Ok(())
+    }
+}
+
+// The layout of a rules file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesRecord {
+    positions: Vec<PositionRule>,
+    bench: usize,
+    budget: Option<f32>,
+    club_cap: Option<usize>,
+}
+
+/// The contents of a rules file: the squad rules and, if it sets them, the
+/// budget and club cap.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RulesFile {
+    pub rules: SquadRules,
+    pub budget: Option<f32>,
+    pub club_cap: Option<usize>,
+}
+
+/// Read squad rules from a TOML file, or a JSON one if the name ends in
+/// `.json`, and check them with `SquadRules::validate`.
+///
+/// ```toml
+/// budget = 1000
+/// club_cap = 3
+/// bench = 4
+///
+/// [[positions]]
+/// name = "GK"
+/// aliases = ["GKP"]
+/// squad = 2
+/// xi_min = 1
+/// xi_max = 1
+/// ```
+///
+/// and a `[[positions]]` table for each other position.
+pub fn read_rules(path: &str) -> Result<RulesFile, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let is_json = Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let record: RulesRecord = if is_json {
+        serde_json::from_str(&text).map_err(|err| format!("{}: {}", path, err))?
+    } else {
+        toml::from_str(&text).map_err(|err| format!("{}: {}", path, err.message()))?
+    };
+
+    let rules = SquadRules {
+        positions: record.positions,
+        bench: record.bench,
+    };
+    rules.validate()?;
+    if let Some(budget) = record.budget.filter(|b| !b.is_finite() || *b < 0.0) {
+        return Err(format!("The budget of {} is not a non-negative number", budget).into());
+    }
+    if record.club_cap == Some(0) {
+        return Err("The club cap must be at least 1".into());
+    }
+    Ok(RulesFile {
+        rules,
+        budget: record.budget,
+        club_cap: record.club_cap,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    const FPL_TOML: &str = r#"
+budget = 1000
+club_cap = 3
+bench = 4
+
+[[positions]]
+name = "GK"
+squad = 2
+xi_min = 1
+xi_max = 1
+
+[[positions]]
+name = "DEF"
+squad = 5
+xi_min = 3
+xi_max = 5
+
+[[positions]]
+name = "MID"
+squad = 5
+xi_min = 2
+xi_max = 5
+
+[[positions]]
+name = "FWD"
+squad = 3
+xi_min = 1
+xi_max = 3
+"#;
+
+    const DRAFT_JSON: &str = r#"{
+  "bench": 3,
+  "club_cap": 2,
+  "positions": [
+    {"name": "GK", "squad": 1, "xi_min": 1, "xi_max": 1},
+    {"name": "DEF", "aliases": ["D"], "squad": 4, "xi_min": 3, "xi_max": 4},
+    {"name": "MID", "aliases": ["M"], "squad": 4, "xi_min": 3, "xi_max": 4},
+    {"name": "FWD", "aliases": ["F"], "squad": 3, "xi_min": 1, "xi_max": 3}
+  ]
+}"#;
+
+    fn read(name: &str, contents: &str) -> Result<RulesFile, String> {
+        let path = env::temp_dir().join(format!(
+            "team_selector_rules_{}_{}",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, contents).unwrap();
+        let rules = read_rules(path.to_str().unwrap()).map_err(|e| e.to_string());
+        fs::remove_file(&path).unwrap();
+        rules
+    }
+
+    #[test]
+    fn reads_toml_and_json() {
+        let fpl = read("fpl.toml", FPL_TOML).unwrap();
+        assert_eq!(fpl.rules, SquadRules::default());
+        assert_eq!((fpl.budget, fpl.club_cap), (Some(1000.0), Some(3)));
+
+        let draft = read("draft.json", DRAFT_JSON).unwrap();
+        assert_eq!(draft.rules.squad_size(), 12);
+        assert_eq!(draft.rules.starters(), 9);
+        assert_eq!(draft.rules.position_name("M"), Some("MID"));
+        assert_eq!(draft.rules.position_index("MID"), Some(2));
+        assert_eq!((draft.budget, draft.club_cap), (None, Some(2)));
+    }
+
+    #[test]
+    fn rejects_impossible_rules() {
+        let unknown_field = FPL_TOML.replace("xi_max = 1", "xi_most = 1");
+        assert!(read("typo.toml", &unknown_field)
+            .unwrap_err()
+            .contains("xi_most"));
+
+        let big_bench = FPL_TOML.replace("bench = 4", "bench = 9");
+        assert!(read("bench.toml", &big_bench)
+            .unwrap_err()
+            .contains("starters"));
+
+        let shared_alias = FPL_TOML.replace("\"FWD\"", "\"FWD\"\naliases = [\"GK\"]");
+        assert!(read("alias.toml", &shared_alias)
+            .unwrap_err()
+            .contains("'GK'"));
+
+        let mut rules = SquadRules::default();
+        rules.positions[0].xi_max = 3;
+        assert!(rules.validate().is_err());
+    }
+}
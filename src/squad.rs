@@ -0,0 +1,670 @@
+use crate::ga::ParentSelection;
+use crate::player::Player;
+use crate::rules::SquadRules;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+// Default genetic algorithm parameters and squad rules
+const POPULATION_SIZE: usize = 150;
+const GENERATIONS: usize = 2500;
+const MUTATION_RATE: f32 = 0.05;
+const TOURNAMENT_SIZE: usize = 3;
+const ELITE_COUNT: usize = 2;
+const HALL_OF_FAME_SIZE: usize = 5;
+const MIGRATION_INTERVAL: usize = 50;
+const MIGRATION_SIZE: usize = 2;
+const MAX_VALUE: f32 = 1000.0;
+const MAX_PLAYERS_PER_TEAM: usize = 3;
+// Weight applied to the players left out of the starting XI
+pub(crate) const BENCH_WEIGHT: f32 = 0.25;
+/// Captain points multiplier in a normal week.
+pub const CAPTAIN_MULTIPLIER: f32 = 2.0;
+/// Captain points multiplier in a triple-captain week.
+pub const TRIPLE_CAPTAIN_MULTIPLIER: f32 = 3.0;
+
+/// Squad rules and optimiser settings shared by every engine.
+#[derive(Debug, Clone)]
+pub struct SelectionConfig {
+    /// Positions, squad counts and formations.
+    pub rules: SquadRules,
+    /// Total budget for the squad, in the units of `Player::value`.
+    pub max_value: f32,
+    /// Maximum number of players from any one club.
+    pub max_players_per_team: usize,
+    /// Multiplier applied to the captain's points.
+    pub captain_multiplier: f32,
+    /// Number of squads in each generation of each island of the genetic
+    /// algorithm.
+    pub population_size: usize,
+    /// Number of generations the genetic algorithm runs for.
+    pub generations: usize,
+    /// Probability that each player of a child squad is swapped out.
+    pub mutation_rate: f32,
+    /// How parents are picked for each child.
+    pub selection: ParentSelection,
+    /// Squads drawn for each tournament when `selection` is `Tournament`.
+    pub tournament_size: usize,
+    /// Best squads of each generation carried into the next unchanged, at
+    /// most `population_size - 1` so that every generation breeds a child.
+    pub elite_count: usize,
+    /// Distinct best squads kept in the hall of fame.
+    pub hall_of_fame_size: usize,
+    /// Populations evolving side by side.
+    pub islands: usize,
+    /// Generations between migrations from each island to the next.
+    pub migration_interval: usize,
+    /// Best squads of each island copied to the next at a migration.
+    pub migration_size: usize,
+    /// Stop once the best fitness has not improved for this many generations.
+    pub patience: Option<usize>,
+    /// Stop once a squad reaches this fitness.
+    pub target_fitness: Option<f32>,
+    /// Stop once the genetic algorithm has run for this long.
+    pub time_limit: Option<Duration>,
+    /// Stop once the share of distinct squads in the population falls below
+    /// this.
+    pub min_diversity: Option<f32>,
+    /// Seed for the genetic algorithm's random numbers, or `None` for a
+    /// different run every time.
+    pub seed: Option<u64>,
+    /// `element` ids of players every squad must include.
+    pub locked: HashSet<u32>,
+    /// `element` ids of players no squad may include.
+    pub excluded: HashSet<u32>,
+    /// Draw a progress bar on the terminal while the genetic algorithm or
+    /// simulated annealing runs.
+    pub progress: bool,
+}
+
+impl Default for SelectionConfig {
+    fn default() -> Self {
+        SelectionConfig {
+            rules: SquadRules::default(),
+            max_value: MAX_VALUE,
+            max_players_per_team: MAX_PLAYERS_PER_TEAM,
+            captain_multiplier: CAPTAIN_MULTIPLIER,
+            population_size: POPULATION_SIZE,
+            generations: GENERATIONS,
+            mutation_rate: MUTATION_RATE,
+            selection: ParentSelection::Truncation,
+            tournament_size: TOURNAMENT_SIZE,
+            elite_count: ELITE_COUNT,
+            hall_of_fame_size: HALL_OF_FAME_SIZE,
+            islands: 1,
+            migration_interval: MIGRATION_INTERVAL,
+            migration_size: MIGRATION_SIZE,
+            patience: None,
+            target_fitness: None,
+            time_limit: None,
+            min_diversity: None,
+            seed: None,
+            locked: HashSet::new(),
+            excluded: HashSet::new(),
+            progress: false,
+        }
+    }
+}
+
+// The players of each position that are not excluded
+pub(crate) fn players_by_position<'a>(
+    players: &'a [Player],
+    config: &SelectionConfig,
+) -> HashMap<&'a str, Vec<&'a Player>> {
+    let mut pools: HashMap<&str, Vec<&Player>> = HashMap::new();
+    for player in players {
+        if !config.excluded.contains(&player.element) {
+            pools
+                .entry(player.position.as_str())
+                .or_default()
+                .push(player);
+        }
+    }
+    pools
+}
+
+// A progress bar of `len` steps, hidden unless `config.progress` is set
+pub(crate) fn progress_bar(len: u64, config: &SelectionConfig) -> ProgressBar {
+    if !config.progress {
+        return ProgressBar::hidden();
+    }
+    let progress_bar = ProgressBar::new(len);
+    progress_bar.set_style(
+        ProgressStyle::default_bar().template(
+            "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
+        ),
+    );
+    progress_bar
+}
+
+// Minimum and maximum starters of a position, none for an unknown one
+fn starting_limits(rules: &SquadRules, position: &str) -> (usize, usize) {
+    rules
+        .position(position)
+        .map_or((0, 0), |rule| (rule.xi_min, rule.xi_max))
+}
+
+// Pick the highest-scoring legal starting XI, returning indices into the team
+pub(crate) fn pick_starting_xi(team: &[Player], rules: &SquadRules) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..team.len()).collect();
+    order.sort_by(|&a, &b| {
+        team[b]
+            .predicted_points
+            .total_cmp(&team[a].predicted_points)
+    });
+
+    // Cover each position's minimum with its best players, then fill the
+    // remaining places with the best of the rest up to each maximum
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut starters = Vec::new();
+    for &i in &order {
+        let (min, _) = starting_limits(rules, &team[i].position);
+        let count = counts.entry(team[i].position.as_str()).or_insert(0);
+        if *count < min {
+            *count += 1;
+            starters.push(i);
+        }
+    }
+    for &i in &order {
+        if starters.len() == rules.starters() {
+            break;
+        }
+        let (_, max) = starting_limits(rules, &team[i].position);
+        let count = counts.entry(team[i].position.as_str()).or_insert(0);
+        if !starters.contains(&i) && *count < max {
+            *count += 1;
+            starters.push(i);
+        }
+    }
+    starters
+}
+
+// Captain and vice-captain are the two highest-scoring starters; a lone
+// starter is both, and a team without starters has neither
+pub(crate) fn pick_captains(team: &[Player], starters: &[usize]) -> Option<(usize, usize)> {
+    let mut order = starters.to_vec();
+    order.sort_by(|&a, &b| {
+        team[b]
+            .predicted_points
+            .total_cmp(&team[a].predicted_points)
+    });
+    let captain = *order.first()?;
+    Some((captain, order.get(1).copied().unwrap_or(captain)))
+}
+
+// Formation of the starters as the count of each position whose number of
+// starters can vary, e.g. 3-4-3 for DEF-MID-FWD
+pub(crate) fn formation(team: &[Player], starters: &[usize], rules: &SquadRules) -> String {
+    rules
+        .positions
+        .iter()
+        .filter(|rule| rule.xi_min < rule.xi_max)
+        .map(|rule| {
+            starters
+                .iter()
+                .filter(|&&i| team[i].position == rule.name)
+                .count()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Score a squad: starters in full, the bench discounted, the captain
+/// multiplied, and zero for a squad over budget.
+pub fn fitness(team: &[Player], config: &SelectionConfig) -> f32 {
+    let total_value: f32 = team.iter().map(|p| p.value).sum();
+    if total_value > config.max_value {
+        return 0.0;
+    }
+    let starters = pick_starting_xi(team, &config.rules);
+    let captain = pick_captains(team, &starters).map(|(captain, _)| captain);
+
+    let points: f32 = team
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            if starters.contains(&i) {
+                p.predicted_points
+            } else {
+                p.predicted_points * BENCH_WEIGHT
+            }
+        })
+        .sum();
+
+    // The captain's points already count once as a starter
+    match captain {
+        Some(captain) => {
+            points + team[captain].predicted_points * (config.captain_multiplier - 1.0)
+        }
+        None => points,
+    }
+}
+
+/// Check that a squad is distinct players filling every position's places,
+/// within the position limits, club cap and budget, with every locked player
+/// and no excluded one.
+pub fn satisfies_constraints(team: &[Player], config: &SelectionConfig) -> bool {
+    if team.iter().any(|p| config.excluded.contains(&p.element))
+        || config
+            .locked
+            .iter()
+            .any(|e| !team.iter().any(|p| p.element == *e))
+    {
+        return false;
+    }
+
+    let mut team_counts = HashMap::new();
+    let mut position_counts = HashMap::new();
+    let mut total_value = 0.0;
+
+    let mut unique_elements = HashMap::new();
+    for player in team {
+        if unique_elements.contains_key(&player.element) {
+            return false;
+        }
+        unique_elements.insert(player.element, true);
+    }
+
+    for player in team {
+        *position_counts.entry(player.position.clone()).or_insert(0) += 1;
+        *team_counts.entry(player.team.clone()).or_insert(0) += 1;
+        total_value += player.value;
+
+        // Players of a position without a squad limit are never allowed
+        let max_pos = config
+            .rules
+            .position(&player.position)
+            .map_or(0, |r| r.squad);
+        if position_counts[&player.position] > max_pos {
+            return false;
+        }
+        if team_counts[&player.team] > config.max_players_per_team || total_value > config.max_value
+        {
+            return false;
+        }
+    }
+    team.len() == config.rules.squad_size()
+}
+
+/// Check that the locked players exist, are not also excluded, and fit in one
+/// squad together.
+pub fn check_player_lists(players: &[Player], config: &SelectionConfig) -> Result<(), String> {
+    let mut team: Vec<&Player> = Vec::new();
+    for &element in &config.locked {
+        if config.excluded.contains(&element) {
+            return Err(format!("Player {} is both locked and excluded", element));
+        }
+        match players.iter().find(|p| p.element == element) {
+            Some(player) => team.push(player),
+            None => {
+                return Err(format!(
+                    "Locked player {} is not in the player list",
+                    element
+                ))
+            }
+        }
+    }
+
+    let mut position_counts: HashMap<&str, usize> = HashMap::new();
+    let mut team_counts: HashMap<&str, usize> = HashMap::new();
+    for player in &team {
+        *position_counts.entry(player.position.as_str()).or_insert(0) += 1;
+        *team_counts.entry(player.team.as_str()).or_insert(0) += 1;
+    }
+    for (position, count) in position_counts {
+        let max = config.rules.position(position).map_or(0, |r| r.squad);
+        if count > max {
+            return Err(format!(
+                "{} locked {} players, but a squad holds at most {}",
+                count, position, max
+            ));
+        }
+    }
+    for (club, count) in team_counts {
+        if count > config.max_players_per_team {
+            return Err(format!(
+                "{} locked players from {}, over the club cap of {}",
+                count, club, config.max_players_per_team
+            ));
+        }
+    }
+    let total_value: f32 = team.iter().map(|p| p.value).sum();
+    if total_value > config.max_value {
+        return Err(format!(
+            "Locked players cost {}, over the budget of {}",
+            total_value, config.max_value
+        ));
+    }
+    Ok(())
+}
+
+/// Check that some valid squad can be picked from `players`, explaining the
+/// first squad rule that cannot be met: the locked players as in
+/// `check_player_lists`, then each position's places, the club cap and the
+/// budget. A squad can still be impossible when the club cap and budget only
+/// fail together, which the squad builders report instead.
+pub fn check_feasibility(players: &[Player], config: &SelectionConfig) -> Result<(), String> {
+    check_player_lists(players, config)?;
+
+    // Each selectable player once, by position and club
+    let mut seen = HashSet::new();
+    let mut available: Vec<&Player> = players
+        .iter()
+        .filter(|p| !config.excluded.contains(&p.element) && seen.insert(p.element))
+        .filter(|p| config.rules.position(&p.position).is_some())
+        .collect();
+    available.sort_by(|a, b| a.value.total_cmp(&b.value));
+    let club_cap = config.max_players_per_team;
+    let under_cap = |players: &mut dyn Iterator<Item = &&Player>| {
+        let mut clubs: HashMap<&str, usize> = HashMap::new();
+        for player in players {
+            *clubs.entry(player.team.as_str()).or_default() += 1;
+        }
+        clubs
+            .values()
+            .map(|&count| count.min(club_cap))
+            .sum::<usize>()
+    };
+
+    let mut cheapest = 0.0;
+    for rule in &config.rules.positions {
+        let of_position = || available.iter().filter(|p| p.position == rule.name);
+        let count = of_position().count();
+        if count < rule.squad {
+            return Err(format!(
+                "Only {} {} players can be picked, but a squad needs {}",
+                count, rule.name, rule.squad
+            ));
+        }
+        let count = under_cap(&mut of_position());
+        if count < rule.squad {
+            return Err(format!(
+                "Only {} {} players can be picked under the club cap of {}, but a squad needs {}",
+                count, rule.name, club_cap, rule.squad
+            ));
+        }
+
+        // The locked players and the cheapest of the rest
+        let (locked, others): (Vec<&&Player>, Vec<&&Player>) =
+            of_position().partition(|p| config.locked.contains(&p.element));
+        let open = rule.squad.saturating_sub(locked.len());
+        cheapest += locked.iter().map(|p| p.value).sum::<f32>();
+        cheapest += others.iter().take(open).map(|p| p.value).sum::<f32>();
+    }
+
+    let squad_size = config.rules.squad_size();
+    let count = under_cap(&mut available.iter());
+    if count < squad_size {
+        return Err(format!(
+            "Only {} players can be picked under the club cap of {}, but a squad needs {}",
+            count, club_cap, squad_size
+        ));
+    }
+    if cheapest > config.max_value {
+        return Err(format!(
+            "The cheapest squad costs {}, over the budget of {}",
+            cheapest, config.max_value
+        ));
+    }
+    Ok(())
+}
+
+// Display order of positions, unknown positions last
+fn position_rank(rules: &SquadRules, position: &str) -> usize {
+    rules.position_index(position).unwrap_or(usize::MAX)
+}
+
+/// A selected squad broken down into starting XI, bench and captaincy.
+#[derive(Debug, Clone)]
+pub struct SelectionResult {
+    /// All players in the squad.
+    pub squad: Vec<Player>,
+    /// The highest-scoring legal starting XI, ordered by position as in the
+    /// squad rules.
+    pub starting_xi: Vec<Player>,
+    /// The remaining players, best first.
+    pub bench: Vec<Player>,
+    pub captain: Player,
+    pub vice_captain: Player,
+    /// The squad's `fitness`.
+    pub score: f32,
+}
+
+impl SelectionResult {
+    /// Pick the starting XI, bench and captaincy for a valid squad.
+    ///
+    /// # Panics
+    ///
+    /// If no player of the squad can start, so there is no captain.
+    pub fn new(squad: Vec<Player>, config: &SelectionConfig) -> Self {
+        let starters = pick_starting_xi(&squad, &config.rules);
+        let (captain, vice_captain) =
+            pick_captains(&squad, &starters).expect("a squad with no possible starters");
+
+        let mut starting_xi: Vec<Player> = starters.iter().map(|&i| squad[i].clone()).collect();
+        starting_xi.sort_by(|a, b| {
+            position_rank(&config.rules, &a.position)
+                .cmp(&position_rank(&config.rules, &b.position))
+                .then(b.predicted_points.total_cmp(&a.predicted_points))
+        });
+
+        let mut bench: Vec<Player> = (0..squad.len())
+            .filter(|i| !starters.contains(i))
+            .map(|i| squad[i].clone())
+            .collect();
+        bench.sort_by(|a, b| b.predicted_points.total_cmp(&a.predicted_points));
+
+        SelectionResult {
+            starting_xi,
+            bench,
+            captain: squad[captain].clone(),
+            vice_captain: squad[vice_captain].clone(),
+            score: fitness(&squad, config),
+            squad,
+        }
+    }
+
+    /// Formation of the starting XI as the number of starters of each
+    /// position whose count can vary, e.g. 3-4-3 for DEF-MID-FWD.
+    pub fn formation(&self, config: &SelectionConfig) -> String {
+        let starters: Vec<usize> = (0..self.starting_xi.len()).collect();
+        formation(&self.starting_xi, &starters, &config.rules)
+    }
+
+    /// Predicted points of the starting XI including the captain's multiplier.
+    pub fn expected_points(&self, config: &SelectionConfig) -> f32 {
+        let starting_points: f32 = self.starting_xi.iter().map(|p| p.predicted_points).sum();
+        starting_points + self.captain.predicted_points * (config.captain_multiplier - 1.0)
+    }
+
+    /// Total value of the squad.
+    pub fn total_value(&self) -> f32 {
+        self.squad.iter().map(|p| p.value).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, check_squad, player};
+    use crate::ga::generate_random_team;
+    use proptest::prelude::*;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn accepts_a_valid_squad() {
+        let config = SelectionConfig::default();
+        assert!(satisfies_constraints(&fixtures::squad(), &config));
+    }
+
+    #[test]
+    fn rejects_a_player_picked_twice() {
+        // Far apart, so only a check across the whole squad finds them
+        let mut team = fixtures::squad();
+        team[14] = team[0].clone();
+        team[14].position = "FWD".to_string();
+        assert!(!satisfies_constraints(&team, &SelectionConfig::default()));
+    }
+
+    #[test]
+    fn rejects_wrong_position_counts() {
+        let config = SelectionConfig::default();
+        let mut team = fixtures::squad();
+        team[2].position = "GK".to_string();
+        assert!(!satisfies_constraints(&team, &config));
+    }
+
+    #[test]
+    fn rejects_players_of_unknown_positions() {
+        // A position the rules do not list has no places in the squad, so
+        // its players are rejected rather than ignored
+        let mut team = fixtures::squad();
+        team[2].position = "COACH".to_string();
+        assert!(!satisfies_constraints(&team, &SelectionConfig::default()));
+    }
+
+    #[test]
+    fn rejects_a_short_squad() {
+        let team = &fixtures::squad()[..14];
+        assert!(!satisfies_constraints(team, &SelectionConfig::default()));
+    }
+
+    #[test]
+    fn rejects_too_many_players_from_a_club() {
+        let mut team = fixtures::squad();
+        team[1].team = team[0].team.clone();
+        assert!(satisfies_constraints(&team, &SelectionConfig::default()));
+        team[2].team = team[0].team.clone();
+        assert!(!satisfies_constraints(&team, &SelectionConfig::default()));
+    }
+
+    #[test]
+    fn rejects_a_squad_over_budget() {
+        let config = SelectionConfig {
+            max_value: 749.0,
+            ..SelectionConfig::default()
+        };
+        assert!(!satisfies_constraints(&fixtures::squad(), &config));
+    }
+
+    #[test]
+    fn requires_locked_and_forbids_excluded_players() {
+        let team = fixtures::squad();
+        let config = SelectionConfig {
+            locked: HashSet::from([16]),
+            ..SelectionConfig::default()
+        };
+        assert!(!satisfies_constraints(&team, &config));
+        let config = SelectionConfig {
+            excluded: HashSet::from([3]),
+            ..SelectionConfig::default()
+        };
+        assert!(!satisfies_constraints(&team, &config));
+    }
+
+    #[test]
+    fn checks_locked_players() {
+        let players = fixtures::squad();
+        let lists = |locked: &[u32], excluded: &[u32]| SelectionConfig {
+            locked: locked.iter().copied().collect(),
+            excluded: excluded.iter().copied().collect(),
+            ..SelectionConfig::default()
+        };
+        assert!(check_player_lists(&players, &lists(&[1, 3], &[2])).is_ok());
+        assert!(check_player_lists(&players, &lists(&[1], &[1])).is_err());
+        assert!(check_player_lists(&players, &lists(&[99], &[])).is_err());
+
+        let mut players = players;
+        players.push(player(16, "GK", "club_9", 40.0, 1.0));
+        assert!(check_player_lists(&players, &lists(&[1, 2, 16], &[])).is_err());
+    }
+
+    #[test]
+    fn scores_teams_too_small_for_a_vice_captain() {
+        let config = SelectionConfig::default();
+        assert_eq!(fitness(&[], &config), 0.0);
+        let keeper = [player(1, "GK", "club_0", 40.0, 3.0)];
+        assert_eq!(fitness(&keeper, &config), 6.0);
+        let result = SelectionResult::new(keeper.to_vec(), &config);
+        assert_eq!(result.captain.element, result.vice_captain.element);
+    }
+
+    #[test]
+    fn explains_which_rule_cannot_be_met() {
+        let players = fixtures::players(150, 1);
+        let feasibility = |config: SelectionConfig| check_feasibility(&players, &config);
+        assert_eq!(feasibility(SelectionConfig::default()), Ok(()));
+
+        let goalkeepers = players.iter().filter(|p| p.position == "GK");
+        let excluded = goalkeepers.skip(1).map(|p| p.element).collect();
+        let err = feasibility(SelectionConfig {
+            excluded,
+            ..SelectionConfig::default()
+        });
+        assert_eq!(
+            err.unwrap_err(),
+            "Only 1 GK players can be picked, but a squad needs 2"
+        );
+
+        let err = feasibility(SelectionConfig {
+            max_players_per_team: 1,
+            ..SelectionConfig::default()
+        });
+        assert!(err.unwrap_err().contains("under the club cap of 1"));
+
+        let err = feasibility(SelectionConfig {
+            max_value: 550.0,
+            ..SelectionConfig::default()
+        });
+        assert!(err.unwrap_err().starts_with("The cheapest squad costs"));
+    }
+
+    #[test]
+    fn follows_other_squad_rules() {
+        let config = SelectionConfig {
+            rules: fixtures::draft_rules(),
+            max_players_per_team: 2,
+            ..SelectionConfig::default()
+        };
+        assert!(!satisfies_constraints(&fixtures::squad(), &config));
+
+        let players = fixtures::players(150, 1);
+        let team =
+            generate_random_team(&players, &config, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        assert_eq!(team.len(), 12);
+        assert!(satisfies_constraints(&team, &config));
+
+        let result = SelectionResult::new(team, &config);
+        assert_eq!((result.starting_xi.len(), result.bench.len()), (9, 3));
+        assert_eq!(result.starting_xi[0].position, "GK");
+        let formation: Vec<usize> = result
+            .formation(&config)
+            .split('-')
+            .map(|count| count.parse().unwrap())
+            .collect();
+        assert_eq!(formation.iter().sum::<usize>(), 8);
+    }
+
+    proptest! {
+        #[test]
+        fn agrees_with_the_squad_rules(seed: u64, changes in 0..3usize) {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let players = fixtures::players(150, seed);
+            let mut config = SelectionConfig::default();
+            let mut team = generate_random_team(&players, &config, &mut rng).unwrap();
+            for _ in 0..changes {
+                let index = rng.gen_range(0..team.len());
+                team[index] = players.choose(&mut rng).unwrap().clone();
+            }
+            fixtures::lock_and_exclude(&players, &mut config, &mut rng);
+
+            prop_assert_eq!(
+                satisfies_constraints(&team, &config),
+                check_squad(&team, &config).is_ok()
+            );
+        }
+    }
+}